@@ -0,0 +1,29 @@
+// Submits the password form as JSON and reloads the post once unlocked.
+document.addEventListener("DOMContentLoaded", () => {
+    const form = document.querySelector("form[data-login]");
+    const error = form.querySelector(".error");
+
+    form.addEventListener("submit", async event => {
+        event.preventDefault();
+
+        const data = new FormData(form);
+        const response = await fetch(form.action, {
+            method: "POST",
+            headers: { "Content-Type": "application/json" },
+            body: JSON.stringify({
+                id: data.get("id"),
+                password: data.get("password")
+            })
+        });
+
+        if (response.ok) {
+            window.location.reload();
+            return;
+        }
+
+        error.textContent = response.status === 429
+            ? "Too many attempts, try again later"
+            : "Wrong password";
+        error.hidden = false;
+    });
+});
@@ -0,0 +1,84 @@
+// Typesets math in the browser as MathML: `\(...\)` and `\[...\]` in text,
+// and `code.language-math` elements emitted for ```math blocks.
+(() => {
+    const options = {
+        output: "mathml",
+        throwOnError: false,
+        macros: {
+            "\\RR": "\\mathbb{R}"
+        }
+    };
+
+    const delimiters = [
+        { left: "\\[", right: "\\]", display: true },
+        { left: "\\(", right: "\\)", display: false }
+    ];
+
+    const ignoredTags = ["SCRIPT", "NOSCRIPT", "STYLE", "TEXTAREA", "PRE", "CODE"];
+
+    const render = (tex, display) => {
+        const span = document.createElement("span");
+        katex.render(tex, span, { ...options, displayMode: display });
+        return span;
+    };
+
+    const nextDelimiter = (text, from) => {
+        let found = null;
+        for (const delimiter of delimiters) {
+            const start = text.indexOf(delimiter.left, from);
+            if (start === -1) {
+                continue;
+            }
+
+            const end = text.indexOf(delimiter.right, start + delimiter.left.length);
+            if (end !== -1 && (found === null || start < found.start)) {
+                found = { delimiter, start, end };
+            }
+        }
+        return found;
+    };
+
+    const renderText = node => {
+        const text = node.textContent;
+        const fragment = document.createDocumentFragment();
+        let position = 0;
+        let match;
+
+        while ((match = nextDelimiter(text, position)) !== null) {
+            const { delimiter, start, end } = match;
+            fragment.append(text.slice(position, start));
+            const tex = text.slice(start + delimiter.left.length, end);
+            fragment.append(render(tex, delimiter.display));
+            position = end + delimiter.right.length;
+        }
+
+        if (position > 0) {
+            fragment.append(text.slice(position));
+            node.replaceWith(fragment);
+        }
+    };
+
+    const walk = element => {
+        for (const child of [...element.childNodes]) {
+            if (child.nodeType === Node.TEXT_NODE) {
+                renderText(child);
+            } else if (
+                child.nodeType === Node.ELEMENT_NODE &&
+                !ignoredTags.includes(child.tagName) &&
+                !child.classList.contains("no-math")
+            ) {
+                walk(child);
+            }
+        }
+    };
+
+    document.addEventListener("DOMContentLoaded", () => {
+        walk(document.body);
+
+        document.querySelectorAll("code.language-math").forEach(el => {
+            const display = el.classList.contains("math-display");
+            katex.render(el.textContent, el, { ...options, displayMode: display });
+            el.classList.add("no-math");
+        });
+    });
+})();
@@ -1,4 +1,46 @@
 CREATE TABLE IF NOT EXISTS posts (
     id TEXT PRIMARY KEY,
-    'password' TEXT
+    password TEXT,
+    content TEXT NOT NULL DEFAULT '',
+    expires_at INTEGER,
+    max_views INTEGER,
+    views INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS post_revisions (
+    post_id TEXT NOT NULL REFERENCES posts (id) ON DELETE CASCADE,
+    number INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    author TEXT,
+    PRIMARY KEY (post_id, number)
+);
+
+CREATE TABLE IF NOT EXISTS sessions (
+    id TEXT PRIMARY KEY,
+    created_at INTEGER NOT NULL,
+    last_seen_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS session_posts (
+    session_id TEXT NOT NULL REFERENCES sessions (id) ON DELETE CASCADE,
+    post_id TEXT NOT NULL,
+    PRIMARY KEY (session_id, post_id)
+);
+
+CREATE TABLE IF NOT EXISTS post_lockouts (
+    post_id TEXT PRIMARY KEY,
+    failures INTEGER NOT NULL,
+    last_failure_at INTEGER NOT NULL,
+    locked_until INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    scopes TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    last_used_at INTEGER,
+    revoked_at INTEGER
 );
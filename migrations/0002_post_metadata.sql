@@ -0,0 +1,17 @@
+ALTER TABLE posts ADD COLUMN title TEXT;
+ALTER TABLE posts ADD COLUMN description TEXT;
+ALTER TABLE posts ADD COLUMN author TEXT;
+ALTER TABLE posts ADD COLUMN created_at INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE posts ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
+
+-- Existing posts take their timestamps from their revision history, or
+-- the time of the migration if they have none.
+UPDATE posts SET
+    created_at = COALESCE(
+        (SELECT MIN(created_at) FROM post_revisions WHERE post_id = posts.id),
+        CAST(strftime('%s', 'now') AS INTEGER)
+    ),
+    updated_at = COALESCE(
+        (SELECT MAX(created_at) FROM post_revisions WHERE post_id = posts.id),
+        CAST(strftime('%s', 'now') AS INTEGER)
+    );
@@ -0,0 +1,10 @@
+-- Columns the public index filters and sorts on. `published_at` and `draft`
+-- mirror the front matter of the current content and are kept up to date by
+-- the post store; `unlisted` hides a post from listings but not from its link.
+ALTER TABLE posts ADD COLUMN published_at INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE posts ADD COLUMN draft INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE posts ADD COLUMN unlisted INTEGER NOT NULL DEFAULT 0;
+
+UPDATE posts SET published_at = created_at;
+
+CREATE INDEX IF NOT EXISTS posts_published ON posts (published_at DESC, id DESC);
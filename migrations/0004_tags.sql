@@ -0,0 +1,16 @@
+-- Tags are shared between posts. Tags listed in a post's front matter are
+-- copied here by the post store whenever its content is saved, marked with
+-- `front_matter` so they can be dropped again when the front matter changes.
+CREATE TABLE IF NOT EXISTS tags (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE IF NOT EXISTS post_tags (
+    post_id TEXT NOT NULL REFERENCES posts (id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags (id) ON DELETE CASCADE,
+    front_matter INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (post_id, tag_id)
+);
+
+CREATE INDEX IF NOT EXISTS post_tags_tag ON post_tags (tag_id);
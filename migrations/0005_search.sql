@@ -0,0 +1,16 @@
+-- Full-text index of post titles, descriptions, tags and markdown. Rows are
+-- written by the post store, which strips front matter from the content;
+-- deleting a post removes its row here.
+CREATE VIRTUAL TABLE IF NOT EXISTS post_search USING fts5 (
+    post_id UNINDEXED,
+    title,
+    description,
+    tags,
+    body,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER IF NOT EXISTS post_search_delete AFTER DELETE ON posts
+BEGIN
+    DELETE FROM post_search WHERE post_id = old.id;
+END;
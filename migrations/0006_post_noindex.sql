@@ -0,0 +1,3 @@
+-- Mirrors `noindex` in the front matter of the current content, kept up to
+-- date by the post store, so the sitemap can leave those posts out.
+ALTER TABLE posts ADD COLUMN noindex INTEGER NOT NULL DEFAULT 0;
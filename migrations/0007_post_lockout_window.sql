@@ -0,0 +1,5 @@
+-- Start of the window failures are counted in; it restarts once a failure
+-- arrives after the window has passed.
+ALTER TABLE post_lockouts ADD COLUMN window_start INTEGER NOT NULL DEFAULT 0;
+
+UPDATE post_lockouts SET window_start = last_failure_at;
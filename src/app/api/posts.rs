@@ -0,0 +1,477 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    middleware,
+    response::IntoResponse,
+    routing::{get, post, put},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{
+        auth::{authenticate, require_scope},
+        error::ApiError,
+        state::AppState,
+    },
+    clock::unix_now,
+    post::{
+        entity::{NewPost, Post, PostId, PostUpdate, Revision, Tag},
+        front_matter,
+        store::{PostStore, PostStoreError},
+    },
+    token::entity::{ApiToken, Scope},
+};
+
+const DEFAULT_PAGE_SIZE: u32 = 20;
+const MAX_PAGE_SIZE: u32 = 100;
+
+pub fn router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/", get(handle_list).post(handle_create))
+        .route(
+            "/{id}",
+            get(handle_get).patch(handle_update).delete(handle_delete),
+        )
+        .route(
+            "/{id}/tags/{tag}",
+            put(handle_add_tag).delete(handle_remove_tag),
+        )
+        .route("/{id}/revisions", get(handle_list_revisions))
+        .route("/{id}/revisions/{number}", get(handle_get_revision))
+        .route(
+            "/{id}/revisions/{number}/restore",
+            post(handle_restore_revision),
+        )
+        .route_layer(middleware::from_fn_with_state(state, authenticate))
+}
+
+#[derive(Debug, Serialize)]
+struct PostResponse {
+    id: String,
+    protected: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_views: Option<i64>,
+    views: i64,
+    title: Option<String>,
+    description: Option<String>,
+    author: Option<String>,
+    unlisted: bool,
+    tags: Vec<Tag>,
+    created_at: i64,
+    updated_at: i64,
+    published_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+}
+
+impl PostResponse {
+    fn new(post: Post, tags: Vec<Tag>, content: Option<String>) -> Self {
+        Self {
+            id: post.id.into(),
+            protected: post.password.is_some(),
+            expires_at: post.expires_at,
+            max_views: post.max_views,
+            views: post.views,
+            title: post.title,
+            description: post.description,
+            author: post.author,
+            unlisted: post.unlisted,
+            tags,
+            created_at: post.created_at,
+            updated_at: post.updated_at,
+            published_at: post.published_at,
+            content,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct PostListResponse {
+    posts: Vec<PostResponse>,
+    /// Pass as `after` to fetch the next page; absent on the last page
+    next: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct RevisionResponse {
+    number: i64,
+    created_at: i64,
+    author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+}
+
+impl RevisionResponse {
+    fn new(revision: Revision, with_content: bool) -> Self {
+        Self {
+            number: revision.number,
+            created_at: revision.created_at,
+            author: revision.author,
+            content: with_content.then_some(revision.content),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListQuery {
+    after: Option<String>,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateRequest {
+    id: Option<String>,
+    password: Option<String>,
+    content: String,
+    title: Option<String>,
+    description: Option<String>,
+    author: Option<String>,
+    /// Seconds until the post expires
+    expires_in: Option<u64>,
+    /// Number of reads after which the post is deleted
+    max_views: Option<u32>,
+    /// Keeps the post off the index
+    #[serde(default)]
+    unlisted: bool,
+    /// Added to the tags in the front matter
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateRequest {
+    content: Option<String>,
+    password: Option<String>,
+    #[serde(default)]
+    remove_password: bool,
+    /// An empty string clears the field
+    title: Option<String>,
+    description: Option<String>,
+    author: Option<String>,
+    unlisted: Option<bool>,
+}
+
+fn check_front_matter(content: &str) -> Result<(), ApiError> {
+    front_matter::parse(content)
+        .map(|_| ())
+        .map_err(|error| ApiError::BadRequest(error.to_string()))
+}
+
+fn parse_tag(tag: &str) -> Result<Tag, ApiError> {
+    tag.parse().map_err(ApiError::BadRequest)
+}
+
+/// Treats an empty string like an absent value.
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|value| !value.is_empty())
+}
+
+fn store_error(error: PostStoreError) -> ApiError {
+    match error {
+        PostStoreError::PostNotFound => ApiError::PostNotFound,
+        PostStoreError::RevisionNotFound => ApiError::RevisionNotFound,
+        PostStoreError::FailedToCreatePost(message) => ApiError::Conflict(message),
+        error => {
+            eprintln!("{}", error);
+            ApiError::Internal
+        }
+    }
+}
+
+async fn handle_list(
+    Extension(token): Extension<ApiToken>,
+    State(state): State<AppState>,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<PostListResponse>, ApiError> {
+    require_scope(&token, Scope::Read)?;
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let posts = state
+        .post_store
+        .list(query.after.map(PostId::from), limit)
+        .await
+        .map_err(store_error)?;
+
+    let next = if posts.len() == limit as usize {
+        posts.last().map(|post| post.id.to_string())
+    } else {
+        None
+    };
+
+    let mut responses = Vec::with_capacity(posts.len());
+    for post in posts {
+        let tags = state
+            .post_store
+            .get_tags(post.id.clone())
+            .await
+            .map_err(store_error)?;
+        responses.push(PostResponse::new(post, tags, None));
+    }
+
+    Ok(Json(PostListResponse {
+        posts: responses,
+        next,
+    }))
+}
+
+async fn handle_create(
+    Extension(token): Extension<ApiToken>,
+    State(state): State<AppState>,
+    Json(request): Json<CreateRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    require_scope(&token, Scope::Write)?;
+
+    let id = request.id.map(PostId::from);
+    if let Some(id) = &id {
+        if id.is_reserved() {
+            return Err(ApiError::BadRequest(format!("id '{}' is reserved", id)));
+        }
+
+        if !id.is_valid() {
+            return Err(ApiError::BadRequest(
+                "id may only contain letters, digits, '-' and '_'".to_string(),
+            ));
+        }
+    }
+
+    check_front_matter(&request.content)?;
+    let tags = request
+        .tags
+        .iter()
+        .map(|tag| parse_tag(tag))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if request.max_views == Some(0) {
+        return Err(ApiError::BadRequest(
+            "max_views must be greater than zero".to_string(),
+        ));
+    }
+
+    let new_post = NewPost {
+        id,
+        password: request.password,
+        content: request.content.clone(),
+        expires_at: request
+            .expires_in
+            .map(|seconds| unix_now().saturating_add_unsigned(seconds)),
+        max_views: request.max_views.map(i64::from),
+        title: non_empty(request.title),
+        description: non_empty(request.description),
+        author: non_empty(request.author),
+        unlisted: request.unlisted,
+        tags,
+    };
+
+    let id = state
+        .post_store
+        .create(new_post, Some(token.name.clone()))
+        .await
+        .map_err(store_error)?;
+
+    let post = state
+        .post_store
+        .get_by_id(id.clone())
+        .await
+        .map_err(store_error)?;
+    let tags = state.post_store.get_tags(id).await.map_err(store_error)?;
+    Ok((
+        StatusCode::CREATED,
+        Json(PostResponse::new(post, tags, Some(request.content))),
+    ))
+}
+
+async fn handle_get(
+    Extension(token): Extension<ApiToken>,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<PostResponse>, ApiError> {
+    require_scope(&token, Scope::Read)?;
+
+    let post = state
+        .post_store
+        .get_by_id(id.into())
+        .await
+        .map_err(store_error)?;
+
+    let content = state
+        .post_store
+        .get_content(post.id.clone())
+        .await
+        .map_err(store_error)?;
+    let tags = state
+        .post_store
+        .get_tags(post.id.clone())
+        .await
+        .map_err(store_error)?;
+
+    Ok(Json(PostResponse::new(post, tags, Some(content))))
+}
+
+async fn handle_update(
+    Extension(token): Extension<ApiToken>,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<UpdateRequest>,
+) -> Result<Json<PostResponse>, ApiError> {
+    require_scope(&token, Scope::Write)?;
+
+    if request.remove_password && request.password.is_some() {
+        return Err(ApiError::BadRequest(
+            "password and remove_password are mutually exclusive".to_string(),
+        ));
+    }
+
+    if let Some(content) = &request.content {
+        check_front_matter(content)?;
+    }
+
+    let password = match request.password {
+        Some(password) => Some(Some(password)),
+        None if request.remove_password => Some(None),
+        None => None,
+    };
+    let clear_empty = |value: Option<String>| value.map(|value| non_empty(Some(value)));
+    let update = PostUpdate {
+        content: request.content.clone(),
+        password,
+        title: clear_empty(request.title),
+        description: clear_empty(request.description),
+        author: clear_empty(request.author),
+        unlisted: request.unlisted,
+    };
+
+    let id = PostId::from(id);
+    state
+        .post_store
+        .update(id.clone(), update, Some(token.name.clone()))
+        .await
+        .map_err(store_error)?;
+
+    let post = state.post_store.get_by_id(id).await.map_err(store_error)?;
+    let tags = state
+        .post_store
+        .get_tags(post.id.clone())
+        .await
+        .map_err(store_error)?;
+    Ok(Json(PostResponse::new(post, tags, request.content)))
+}
+
+async fn handle_add_tag(
+    Extension(token): Extension<ApiToken>,
+    State(state): State<AppState>,
+    Path((id, tag)): Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    require_scope(&token, Scope::Write)?;
+
+    let tag = parse_tag(&tag)?;
+    state
+        .post_store
+        .tag(id.into(), vec![tag])
+        .await
+        .map_err(store_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn handle_remove_tag(
+    Extension(token): Extension<ApiToken>,
+    State(state): State<AppState>,
+    Path((id, tag)): Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    require_scope(&token, Scope::Write)?;
+
+    let tag = parse_tag(&tag)?;
+    state
+        .post_store
+        .untag(id.into(), vec![tag])
+        .await
+        .map_err(store_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn handle_delete(
+    Extension(token): Extension<ApiToken>,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    require_scope(&token, Scope::Delete)?;
+
+    if state
+        .post_store
+        .delete_by_id(id.into())
+        .await
+        .map_err(store_error)?
+    {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::PostNotFound)
+    }
+}
+
+async fn handle_list_revisions(
+    Extension(token): Extension<ApiToken>,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<RevisionResponse>>, ApiError> {
+    require_scope(&token, Scope::Read)?;
+
+    let revisions = state
+        .post_store
+        .list_revisions(id.into())
+        .await
+        .map_err(store_error)?;
+
+    Ok(Json(
+        revisions
+            .into_iter()
+            .map(|revision| RevisionResponse::new(revision, false))
+            .collect(),
+    ))
+}
+
+async fn handle_get_revision(
+    Extension(token): Extension<ApiToken>,
+    State(state): State<AppState>,
+    Path((id, number)): Path<(String, i64)>,
+) -> Result<Json<RevisionResponse>, ApiError> {
+    require_scope(&token, Scope::Read)?;
+
+    let revision = state
+        .post_store
+        .get_revision(id.into(), number)
+        .await
+        .map_err(store_error)?;
+
+    Ok(Json(RevisionResponse::new(revision, true)))
+}
+
+async fn handle_restore_revision(
+    Extension(token): Extension<ApiToken>,
+    State(state): State<AppState>,
+    Path((id, number)): Path<(String, i64)>,
+) -> Result<Json<RevisionResponse>, ApiError> {
+    require_scope(&token, Scope::Write)?;
+
+    let id = PostId::from(id);
+    let restored = state
+        .post_store
+        .restore_revision(id.clone(), number, Some(token.name.clone()))
+        .await
+        .map_err(store_error)?;
+
+    let revision = state
+        .post_store
+        .get_revision(id, restored)
+        .await
+        .map_err(store_error)?;
+
+    Ok(Json(RevisionResponse::new(revision, true)))
+}
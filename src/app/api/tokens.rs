@@ -0,0 +1,98 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    middleware,
+    response::IntoResponse,
+    routing::{delete, get},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{
+        auth::{authenticate, require_scope},
+        error::ApiError,
+        state::AppState,
+    },
+    token::{
+        entity::{ApiToken, Scope, TokenId},
+        store::{TokenStore, TokenStoreError},
+    },
+};
+
+pub fn router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/", get(handle_list).post(handle_create))
+        .route("/{id}", delete(handle_revoke))
+        .route_layer(middleware::from_fn_with_state(state, authenticate))
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateRequest {
+    name: String,
+    scopes: Vec<Scope>,
+}
+
+#[derive(Debug, Serialize)]
+struct CreateResponse {
+    #[serde(flatten)]
+    token: ApiToken,
+    /// Shown only once; only its hash is stored
+    secret: String,
+}
+
+fn store_error(error: TokenStoreError) -> ApiError {
+    eprintln!("{}", error);
+    ApiError::Internal
+}
+
+async fn handle_list(
+    Extension(token): Extension<ApiToken>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ApiToken>>, ApiError> {
+    require_scope(&token, Scope::Admin)?;
+
+    let tokens = state.token_store.list().await.map_err(store_error)?;
+    Ok(Json(tokens))
+}
+
+async fn handle_create(
+    Extension(token): Extension<ApiToken>,
+    State(state): State<AppState>,
+    Json(request): Json<CreateRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    require_scope(&token, Scope::Admin)?;
+
+    if request.name.trim().is_empty() || request.scopes.is_empty() {
+        return Err(ApiError::BadRequest(
+            "a token needs a name and at least one scope".to_string(),
+        ));
+    }
+
+    let (token, secret) = state
+        .token_store
+        .create(request.name, request.scopes)
+        .await
+        .map_err(store_error)?;
+
+    Ok((StatusCode::CREATED, Json(CreateResponse { token, secret })))
+}
+
+async fn handle_revoke(
+    Extension(token): Extension<ApiToken>,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    require_scope(&token, Scope::Admin)?;
+
+    if state
+        .token_store
+        .revoke(TokenId::from(id))
+        .await
+        .map_err(store_error)?
+    {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::TokenNotFound)
+    }
+}
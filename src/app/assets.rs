@@ -0,0 +1,24 @@
+/// A front-end file compiled into the binary and served under `/assets`.
+pub struct Asset {
+    pub content_type: &'static str,
+    pub body: &'static [u8],
+}
+
+const JAVASCRIPT: &str = "text/javascript; charset=utf-8";
+const CSS: &str = "text/css; charset=utf-8";
+
+/// Looks up an asset by its path below `/assets/`.
+pub fn find(path: &str) -> Option<Asset> {
+    let (content_type, body): (&str, &[u8]) = match path {
+        "css/fonts.css" => (CSS, include_bytes!("../../assets/css/fonts.css")),
+        "js/login.js" => (JAVASCRIPT, include_bytes!("../../assets/js/login.js")),
+        "js/math.js" => (JAVASCRIPT, include_bytes!("../../assets/js/math.js")),
+        "katex/katex.min.js" => (
+            JAVASCRIPT,
+            include_bytes!("../../assets/katex/katex.min.js"),
+        ),
+        _ => return None,
+    };
+
+    Some(Asset { content_type, body })
+}
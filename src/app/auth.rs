@@ -0,0 +1,55 @@
+use axum::{
+    extract::{Request, State},
+    http::header::AUTHORIZATION,
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{
+    app::{error::ApiError, state::AppState},
+    token::{
+        entity::{ApiToken, Scope},
+        store::TokenStore,
+    },
+};
+
+fn bearer_token(request: &Request) -> Option<&str> {
+    request
+        .headers()
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+/// Resolves the bearer token of a management request and makes the
+/// [`ApiToken`] available to handlers as an extension.
+pub async fn authenticate(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let Some(secret) = bearer_token(&request) else {
+        return Err(ApiError::Unauthorized);
+    };
+
+    let token = match state.token_store.authenticate(secret).await {
+        Ok(Some(token)) => token,
+        Ok(None) => return Err(ApiError::Unauthorized),
+        Err(error) => {
+            eprintln!("failed to authenticate api token: {}", error);
+            return Err(ApiError::Internal);
+        }
+    };
+
+    request.extensions_mut().insert(token);
+    Ok(next.run(request).await)
+}
+
+pub fn require_scope(token: &ApiToken, scope: Scope) -> Result<(), ApiError> {
+    if token.allows(scope) {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden)
+    }
+}
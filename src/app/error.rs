@@ -1,16 +1,41 @@
-use axum::{http::StatusCode, response::IntoResponse};
+use std::time::Duration;
+
+use axum::{
+    http::{StatusCode, header::RETRY_AFTER},
+    response::IntoResponse,
+};
 
 pub enum ApiError {
     PostNotFound,
+    RevisionNotFound,
+    TokenNotFound,
     Unauthorized,
+    Forbidden,
+    TooManyRequests(Duration),
+    BadRequest(String),
+    Conflict(String),
+    Internal,
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
         match self {
-            Self::PostNotFound => (StatusCode::NOT_FOUND, "Post not found"),
-            Self::Unauthorized => (StatusCode::NOT_FOUND, "Unauthorized"),
+            Self::PostNotFound => (StatusCode::NOT_FOUND, "Post not found").into_response(),
+            Self::RevisionNotFound => (StatusCode::NOT_FOUND, "Revision not found").into_response(),
+            Self::TokenNotFound => (StatusCode::NOT_FOUND, "Token not found").into_response(),
+            Self::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized").into_response(),
+            Self::Forbidden => (StatusCode::FORBIDDEN, "Forbidden").into_response(),
+            Self::TooManyRequests(retry_after) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(RETRY_AFTER, retry_after.as_secs().max(1).to_string())],
+                "Too many failed attempts",
+            )
+                .into_response(),
+            Self::BadRequest(message) => (StatusCode::BAD_REQUEST, message).into_response(),
+            Self::Conflict(message) => (StatusCode::CONFLICT, message).into_response(),
+            Self::Internal => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error").into_response()
+            }
         }
-        .into_response()
     }
 }
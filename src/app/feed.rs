@@ -0,0 +1,277 @@
+use atom_syndication::{Category, Content, Entry, Feed, FixedDateTime, Link, Person, Text};
+use axum::{
+    Router,
+    extract::{Path, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use rss::{Channel, Guid, Item};
+
+use crate::{
+    app::{error::ApiError, http::post_title, state::AppState},
+    clock::{format_unix_time, unix_now},
+    post::{entity::Tag, front_matter, store::PostStore},
+};
+
+/// Posts in each feed, newest first.
+const FEED_LEN: u32 = 20;
+
+const FEED_TITLE: &str = "Poster";
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/feed.atom", get(handle_atom))
+        .route("/feed.xml", get(handle_rss))
+        .route("/tags/{tag}/feed.atom", get(handle_tag_atom))
+        .route("/tags/{tag}/feed.xml", get(handle_tag_rss))
+}
+
+/// A published post rendered for a feed.
+struct FeedEntry {
+    url: String,
+    title: String,
+    summary: Option<String>,
+    /// Rendered HTML of the whole post
+    content: String,
+    author: Option<String>,
+    tags: Vec<Tag>,
+    published_at: i64,
+    updated_at: i64,
+}
+
+/// Collects the newest published posts, only those tagged `tag` if given.
+async fn load_entries(state: &AppState, tag: Option<Tag>) -> Result<Vec<FeedEntry>, ApiError> {
+    let store = &state.post_store;
+    let posts = match tag {
+        Some(tag) => store.list_tagged(tag, None, 0, FEED_LEN).await,
+        None => store.list_published(None, 0, FEED_LEN).await,
+    }
+    .map_err(|_| ApiError::Internal)?;
+
+    let mut entries = Vec::with_capacity(posts.len());
+    for post in posts {
+        let Ok(content) = store.get_content(post.id.clone()).await else {
+            continue;
+        };
+        let Ok((front_matter, markdown)) = front_matter::parse(&content) else {
+            continue;
+        };
+        let tags = store
+            .get_tags(post.id.clone())
+            .await
+            .map_err(|_| ApiError::Internal)?;
+
+        let renderer = &state.renderer;
+        let math = renderer.math_mode(front_matter.math);
+        let rendered = renderer.render(markdown, &front_matter.markdown, math, Some(false));
+
+        entries.push(FeedEntry {
+            url: format!("{}/{}", state.base_url, post.id),
+            title: post_title(renderer, &post, &front_matter, markdown),
+            summary: front_matter
+                .description
+                .clone()
+                .or_else(|| post.description.clone())
+                .or_else(|| renderer.excerpt(markdown)),
+            content: rendered.html,
+            author: post.author,
+            tags,
+            published_at: post.published_at,
+            updated_at: post.updated_at,
+        });
+    }
+
+    Ok(entries)
+}
+
+fn datetime(secs: i64) -> FixedDateTime {
+    FixedDateTime::parse_from_rfc3339(&format_unix_time(secs))
+        .expect("format_unix_time writes RFC 3339")
+}
+
+/// The feed is as recent as its most recently changed post.
+fn last_updated(entries: &[FeedEntry]) -> i64 {
+    entries
+        .iter()
+        .map(|entry| entry.updated_at)
+        .max()
+        .unwrap_or_else(unix_now)
+}
+
+fn link(href: String, rel: &str, mime_type: Option<&str>) -> Link {
+    let mut link = Link::default();
+    link.set_href(href);
+    link.set_rel(rel);
+    link.set_mime_type(mime_type.map(String::from));
+    link
+}
+
+fn atom_feed(
+    state: &AppState,
+    title: String,
+    page: &str,
+    feed_path: &str,
+    entries: Vec<FeedEntry>,
+) -> String {
+    let feed_url = format!("{}{}", state.base_url, feed_path);
+
+    let mut feed = Feed::default();
+    feed.set_title(title);
+    feed.set_id(feed_url.clone());
+    feed.set_updated(datetime(last_updated(&entries)));
+    feed.set_links(vec![
+        link(feed_url, "self", Some("application/atom+xml")),
+        link(
+            format!("{}{}", state.base_url, page),
+            "alternate",
+            Some("text/html"),
+        ),
+    ]);
+    feed.set_entries(
+        entries
+            .into_iter()
+            .map(|post| {
+                let mut content = Content::default();
+                content.set_content_type(Some(String::from("html")));
+                content.set_base(Some(post.url.clone()));
+                content.set_value(Some(post.content));
+
+                let mut entry = Entry::default();
+                entry.set_title(post.title);
+                entry.set_id(post.url.clone());
+                entry.set_links(vec![link(post.url, "alternate", Some("text/html"))]);
+                entry.set_published(Some(datetime(post.published_at)));
+                entry.set_updated(datetime(post.updated_at));
+                entry.set_summary(post.summary.map(Text::plain));
+                entry.set_content(Some(content));
+                entry.set_authors(
+                    post.author
+                        .map(|name| Person {
+                            name,
+                            ..Default::default()
+                        })
+                        .into_iter()
+                        .collect::<Vec<_>>(),
+                );
+                entry.set_categories(
+                    post.tags
+                        .into_iter()
+                        .map(|tag| Category {
+                            term: tag.into(),
+                            ..Default::default()
+                        })
+                        .collect::<Vec<_>>(),
+                );
+                entry
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    feed.to_string()
+}
+
+fn rss_channel(state: &AppState, title: String, page: &str, entries: Vec<FeedEntry>) -> String {
+    let mut channel = Channel::default();
+    channel.set_title(title);
+    channel.set_link(format!("{}{}", state.base_url, page));
+    channel.set_description(String::from("Recently published posts"));
+    channel.set_last_build_date(Some(datetime(last_updated(&entries)).to_rfc2822()));
+    channel.set_items(
+        entries
+            .into_iter()
+            .map(|post| {
+                let mut guid = Guid::default();
+                guid.set_value(post.url.clone());
+                guid.set_permalink(true);
+
+                let mut item = Item::default();
+                item.set_title(Some(post.title));
+                item.set_link(Some(post.url));
+                item.set_guid(Some(guid));
+                item.set_pub_date(Some(datetime(post.published_at).to_rfc2822()));
+                item.set_description(post.summary);
+                item.set_content(Some(post.content));
+                item.set_categories(
+                    post.tags
+                        .into_iter()
+                        .map(|tag| rss::Category {
+                            name: tag.into(),
+                            domain: None,
+                        })
+                        .collect::<Vec<_>>(),
+                );
+                item
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    channel.to_string()
+}
+
+fn atom_response(feed: String) -> Response {
+    (
+        [(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
+        feed,
+    )
+        .into_response()
+}
+
+fn rss_response(channel: String) -> Response {
+    (
+        [(header::CONTENT_TYPE, "application/rss+xml; charset=utf-8")],
+        channel,
+    )
+        .into_response()
+}
+
+async fn handle_atom(State(state): State<AppState>) -> Result<Response, ApiError> {
+    let entries = load_entries(&state, None).await?;
+    let feed = atom_feed(&state, FEED_TITLE.to_string(), "/", "/feed.atom", entries);
+
+    Ok(atom_response(feed))
+}
+
+async fn handle_rss(State(state): State<AppState>) -> Result<Response, ApiError> {
+    let entries = load_entries(&state, None).await?;
+    let channel = rss_channel(&state, FEED_TITLE.to_string(), "/", entries);
+
+    Ok(rss_response(channel))
+}
+
+fn parse_tag(tag: &str) -> Result<Tag, ApiError> {
+    Tag::parse(tag).ok_or_else(|| ApiError::BadRequest("invalid tag".to_string()))
+}
+
+async fn handle_tag_atom(
+    State(state): State<AppState>,
+    Path(tag): Path<String>,
+) -> Result<Response, ApiError> {
+    let tag = parse_tag(&tag)?;
+    let entries = load_entries(&state, Some(tag.clone())).await?;
+    let feed = atom_feed(
+        &state,
+        format!("{} #{}", FEED_TITLE, tag),
+        &format!("/tags/{}", tag),
+        &format!("/tags/{}/feed.atom", tag),
+        entries,
+    );
+
+    Ok(atom_response(feed))
+}
+
+async fn handle_tag_rss(
+    State(state): State<AppState>,
+    Path(tag): Path<String>,
+) -> Result<Response, ApiError> {
+    let tag = parse_tag(&tag)?;
+    let entries = load_entries(&state, Some(tag.clone())).await?;
+    let channel = rss_channel(
+        &state,
+        format!("{} #{}", FEED_TITLE, tag),
+        &format!("/tags/{}", tag),
+        entries,
+    );
+
+    Ok(rss_response(channel))
+}
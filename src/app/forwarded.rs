@@ -0,0 +1,105 @@
+use std::net::IpAddr;
+
+use axum::http::HeaderMap;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// The address a request came from. Requests relayed by one of
+/// `trusted_proxies` are traced back through `X-Forwarded-For`, right to left,
+/// to the first hop that is not a trusted proxy; an entry that does not parse
+/// stops the walk at the hop that added it.
+pub fn client_ip(peer: IpAddr, headers: &HeaderMap, trusted_proxies: &[IpAddr]) -> IpAddr {
+    if !trusted_proxies.contains(&peer) {
+        return peer;
+    }
+
+    let forwarded: Vec<&str> = headers
+        .get_all(X_FORWARDED_FOR)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect();
+
+    let mut client = peer;
+    for entry in forwarded.into_iter().rev() {
+        if !trusted_proxies.contains(&client) {
+            break;
+        }
+
+        match entry.trim().parse() {
+            Ok(ip) => client = ip,
+            Err(_) => break,
+        }
+    }
+
+    client
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    const PROXY: &str = "10.0.0.1";
+    const INNER_PROXY: &str = "10.0.0.2";
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    fn headers(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(X_FORWARDED_FOR, HeaderValue::from_str(value).unwrap());
+        }
+
+        headers
+    }
+
+    #[test]
+    fn ignores_header_from_untrusted_peer() {
+        let headers = headers(&["203.0.113.7"]);
+
+        assert_eq!(
+            client_ip(ip("198.51.100.1"), &headers, &[ip(PROXY)]),
+            ip("198.51.100.1")
+        );
+        assert_eq!(client_ip(ip(PROXY), &headers, &[]), ip(PROXY));
+    }
+
+    #[test]
+    fn takes_client_from_trusted_proxy() {
+        let headers = headers(&["203.0.113.7"]);
+
+        assert_eq!(
+            client_ip(ip(PROXY), &headers, &[ip(PROXY)]),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn keeps_peer_without_header() {
+        assert_eq!(
+            client_ip(ip(PROXY), &HeaderMap::new(), &[ip(PROXY)]),
+            ip(PROXY)
+        );
+    }
+
+    #[test]
+    fn skips_only_trusted_hops() {
+        let trusted = [ip(PROXY), ip(INNER_PROXY)];
+
+        // The client made up the first entry; the proxies appended the rest.
+        let headers = headers(&["192.0.2.99, 203.0.113.7", INNER_PROXY]);
+
+        assert_eq!(client_ip(ip(PROXY), &headers, &trusted), ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn stops_at_malformed_entry() {
+        let headers = headers(&["203.0.113.7, unknown"]);
+
+        assert_eq!(client_ip(ip(PROXY), &headers, &[ip(PROXY)]), ip(PROXY));
+    }
+}
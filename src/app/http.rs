@@ -1,33 +1,57 @@
-use std::{collections::HashMap, fs};
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
 
-use askama::Template;
 use axum::{
     Json, Router,
-    extract::{Path, State},
-    http::{HeaderMap, StatusCode, header::COOKIE},
-    response::{AppendHeaders, Html, IntoResponse, Response},
+    extract::{ConnectInfo, Path, Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{Html, IntoResponse, Response},
     routing::{get, post},
 };
-use comrak::{ExtensionOptions, Options, ParseOptions, RenderOptions};
-use serde::Deserialize;
-use sqlx::sqlite::SqlitePoolOptions;
-use tower_http::services::ServeDir;
+use axum_extra::extract::cookie::{CookieJar, SameSite};
+use minijinja::context;
+use serde::{Deserialize, Serialize};
+use tower_http::{services::ServeDir, set_header::SetResponseHeaderLayer};
 
 use crate::{
-    app::{error::ApiError, params::HttpParams, state::AppState},
-    post::store::{PostStore, SqlitePostStore},
-    session::{entity::SessionId, store::MemorySessionStore},
+    app::{
+        api, assets,
+        error::ApiError,
+        feed, forwarded,
+        params::{CookieSameSite, HttpParams, SessionStoreKind},
+        search, sitemap,
+        state::{AppState, AppStateParams},
+        tls,
+    },
+    clock::{format_date, unix_now},
+    database::{self, migrate},
+    limit::{
+        entity::AttemptKey,
+        limiter::AttemptLimiter,
+        store::{LockoutStore, SqliteLockoutStore},
+    },
+    post::{
+        entity::{Post, PostId, PublishedCursor, Tag, TagCount},
+        front_matter::{self, FrontMatter},
+        password::{self, Verification},
+        store::{PostStore, SqlitePostStore},
+        sweeper,
+    },
+    render::{Renderer, config::MathMode, toc::TocEntry},
+    session::{
+        cookie::SessionCookies,
+        gc,
+        store::{MemorySessionStore, SessionStore, SqliteSessionStore},
+    },
+    theme::{Theme, watcher},
+    token::store::SqliteTokenStore,
 };
 
 pub async fn run(params: HttpParams) {
-    let database_url =
-        std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite::memory:".to_string());
-
-    let pool = match SqlitePoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
-        .await
-    {
+    let pool = match database::connect().await {
         Ok(pool) => pool,
         Err(e) => {
             eprintln!("failed to connect to database: {}", e);
@@ -35,203 +59,778 @@ pub async fn run(params: HttpParams) {
         }
     };
 
-    let post_store = SqlitePostStore::new(pool)
-        .await
-        .expect("failed to create sqlite repository");
-    let session_store = MemorySessionStore::new();
-    let app_state = AppState::new(post_store, session_store);
+    match migrate::run(&pool).await {
+        Ok(applied) => {
+            for migration in applied {
+                println!(
+                    "applied migration {} {}",
+                    migration.version, migration.description
+                );
+            }
+        }
+        Err(error) => {
+            eprintln!("failed to migrate database: {}", error);
+            return;
+        }
+    }
+
+    let post_store = Arc::new(SqlitePostStore::new(pool.clone()));
+    sweeper::spawn_sweeper(post_store.clone(), params.post_sweep_interval);
+    let session_store: Arc<dyn SessionStore + Send + Sync> = match params.session_store {
+        SessionStoreKind::Memory => Arc::new(MemorySessionStore::new(params.session_expiry)),
+        SessionStoreKind::Sqlite => {
+            Arc::new(SqliteSessionStore::new(pool.clone(), params.session_expiry))
+        }
+    };
+    gc::spawn_collector(session_store.clone(), params.session_gc_interval);
 
+    let session_key = match params.session_key {
+        Some(key) => key.into_bytes(),
+        None => {
+            eprintln!("no session key configured, sessions will not survive a restart");
+            SessionCookies::random_key()
+        }
+    };
+    let same_site = match params.cookie_same_site {
+        CookieSameSite::Strict => SameSite::Strict,
+        CookieSameSite::Lax => SameSite::Lax,
+        CookieSameSite::None => SameSite::None,
+    };
+    let session_cookies = SessionCookies::new(
+        session_key,
+        params
+            .previous_session_keys
+            .into_iter()
+            .map(String::into_bytes)
+            .collect(),
+        same_site,
+        params.cookie_domain,
+        params.cookie_secure.unwrap_or(params.tls.is_some()),
+        params.session_expiry.lifetime,
+    );
+
+    let lockout_store = SqliteLockoutStore::new(pool.clone());
+    let attempt_limiter = AttemptLimiter::new(params.attempt_limits);
+    let token_store = SqliteTokenStore::new(pool);
+
+    let theme = Arc::new(Theme::new(params.template_path.clone()));
+    if params.dev {
+        match watcher::spawn_watcher(theme.clone(), params.template_path) {
+            Ok(_) => {}
+            Err(error) => eprintln!("failed to watch templates for changes: {}", error),
+        }
+    }
+
+    let base_url = params.base_url.clone().unwrap_or_else(|| {
+        let scheme = if params.tls.is_some() {
+            "https"
+        } else {
+            "http"
+        };
+        format!("{}://{}:{}", scheme, params.host, params.port)
+    });
+    let robots_txt = params
+        .robots_txt
+        .clone()
+        .unwrap_or_else(|| sitemap::default_robots_txt(&base_url));
+    let app_state = AppState::new(AppStateParams {
+        post_store,
+        session_store,
+        session_cookies,
+        attempt_limiter,
+        lockout_store,
+        trusted_proxies: params.trusted_proxies,
+        token_store,
+        renderer: Renderer::new(params.markdown, params.highlight, params.math),
+        theme,
+        posts_per_page: params.posts_per_page,
+        base_url,
+        robots_txt,
+    });
+
+    let content_security_policy = HeaderValue::from_str(&params.content_security_policy)
+        .expect("content security policy is checked by the params builder");
     let static_service = ServeDir::new(params.static_path);
     let router = Router::new()
+        .route("/static/highlight.css", get(handle_highlight_css))
         .nest_service("/static", static_service)
+        .route("/assets/{*path}", get(handle_asset))
+        .route("/", get(handle_index))
+        .route("/page/{number}", get(handle_index_page))
+        .route("/tags", get(handle_tags))
+        .route("/tags/{tag}", get(handle_tag))
+        .route("/tags/{tag}/page/{number}", get(handle_tag_page))
+        .merge(feed::router())
+        .merge(search::router())
+        .merge(sitemap::router())
         .route("/{id}", get(handle_post))
+        .route("/{id}/revisions/{number}", get(handle_revision))
         .route("/api/login", post(handle_login))
-        .with_state(app_state);
+        .nest("/api/posts", api::posts::router(app_state.clone()))
+        .nest("/api/tokens", api::tokens::router(app_state.clone()))
+        .with_state(app_state)
+        .layer(SetResponseHeaderLayer::if_not_present(
+            header::CONTENT_SECURITY_POLICY,
+            content_security_policy,
+        ))
+        .layer(SetResponseHeaderLayer::overriding(
+            header::REFERRER_POLICY,
+            HeaderValue::from_static("same-origin"),
+        ))
+        .layer(SetResponseHeaderLayer::overriding(
+            header::X_CONTENT_TYPE_OPTIONS,
+            HeaderValue::from_static("nosniff"),
+        ));
 
     let addr = format!("{}:{}", params.host, params.port);
-    println!("running app on {}...", addr);
 
-    let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    axum::serve(listener, router).await.unwrap();
+    let Some(tls_files) = params.tls else {
+        println!("running app on {}...", addr);
+
+        let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
+        axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .unwrap();
+        return;
+    };
+
+    let config = match tls::load_config(&tls_files.cert, &tls_files.key).await {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("failed to load tls certificate: {}", error);
+            return;
+        }
+    };
+    tls::spawn_reloader(config.clone(), tls_files.cert, tls_files.key);
+
+    if let Some(redirect_port) = params.http_redirect_port {
+        let redirect_addr = format!("{}:{}", params.host, redirect_port);
+        println!("redirecting http on {}...", redirect_addr);
+
+        let listener = tokio::net::TcpListener::bind(&redirect_addr).await.unwrap();
+        let redirect = tls::redirect_router(params.host.clone(), params.port);
+        tokio::spawn(async move {
+            axum::serve(listener, redirect).await.unwrap();
+        });
+    }
+
+    println!("running app on https://{}...", addr);
+
+    let listener = std::net::TcpListener::bind(&addr).unwrap();
+    listener.set_nonblocking(true).unwrap();
+    axum_server::from_tcp_rustls(listener, config)
+        .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .unwrap();
 }
 
-#[derive(Template)]
-#[template(path = "post.html")]
+#[derive(Serialize)]
 struct PostTemplate {
     id: String,
+    title: String,
+    description: Option<String>,
+    author: Option<String>,
+    created_at: String,
+    /// Only set when the post changed on a later day than it was created
+    updated_at: Option<String>,
+    tags: Vec<Tag>,
     content: String,
+    toc: Option<Vec<TocEntry>>,
     with_password: bool,
+    revision: Option<i64>,
+    /// Load KaTeX to typeset math in the browser
+    client_math: bool,
+    noindex: bool,
+    highlight_stylesheet: bool,
 }
 
-#[derive(Template)]
-#[template(path = "password.html")]
-struct PasswordTemplate {
-    id: String,
+impl PostTemplate {
+    /// Front matter takes precedence over the metadata stored with the post.
+    fn new(
+        renderer: &Renderer,
+        post: Post,
+        front_matter: FrontMatter,
+        tags: Vec<Tag>,
+        markdown: &str,
+        revision: Option<i64>,
+    ) -> Self {
+        let created_at = format_date(front_matter.date.unwrap_or(post.created_at));
+        let math = renderer.math_mode(front_matter.math);
+        let updated_at = Some(format_date(post.updated_at)).filter(|date| *date != created_at);
+        let rendered = renderer.render(markdown, &front_matter.markdown, math, front_matter.toc);
+
+        Self {
+            title: post_title(renderer, &post, &front_matter, markdown),
+            description: front_matter.description.or(post.description),
+            author: post.author,
+            created_at,
+            updated_at,
+            tags,
+            content: rendered.html,
+            toc: rendered.toc,
+            with_password: post.password.is_none(),
+            id: post.id.into(),
+            revision,
+            client_math: math == Some(MathMode::Client),
+            noindex: front_matter.noindex || post.unlisted,
+            highlight_stylesheet: renderer.stylesheet().is_some(),
+        }
+    }
 }
 
-#[derive(Template)]
-#[template(path = "not-found.html")]
-struct NotFoundTemplate;
+/// Front matter, then the stored title, then the first heading, then the id.
+pub fn post_title(
+    renderer: &Renderer,
+    post: &Post,
+    front_matter: &FrontMatter,
+    markdown: &str,
+) -> String {
+    front_matter
+        .title
+        .clone()
+        .or_else(|| post.title.clone())
+        .or_else(|| renderer.first_heading(markdown))
+        .unwrap_or_else(|| post.id.to_string())
+}
 
-fn extract_cookie(headers: HeaderMap, name: &str) -> Option<String> {
-    if let Some(cookies) = headers.get(COOKIE) {
-        let str = cookies.to_str().unwrap_or("");
-        if str.is_empty() {
-            return None;
+#[derive(Serialize)]
+struct IndexTemplate {
+    /// Set on the pages of a single tag
+    tag: Option<Tag>,
+    posts: Vec<PostSummary>,
+    previous_page: Option<String>,
+    next_page: Option<String>,
+}
+
+#[derive(Serialize)]
+struct PostSummary {
+    url: String,
+    title: String,
+    date: String,
+    excerpt: Option<String>,
+}
+
+impl PostSummary {
+    fn new(renderer: &Renderer, post: Post, front_matter: FrontMatter, markdown: &str) -> Self {
+        Self {
+            url: format!("/{}", post.id),
+            title: post_title(renderer, &post, &front_matter, markdown),
+            date: format_date(post.published_at),
+            excerpt: front_matter
+                .description
+                .or(post.description)
+                .or_else(|| renderer.excerpt(markdown)),
         }
+    }
+}
 
-        let cookies: HashMap<String, String> = str
-            .split(';')
-            .map(|pair| pair.trim())
-            .filter_map(|pair| {
-                let mut parts = pair.splitn(2, '=');
-                let key = parts.next()?.to_string();
-                let value = parts.next()?.to_string();
-                Some((key, value))
-            })
-            .collect();
+#[derive(Debug, Deserialize)]
+struct IndexQuery {
+    /// Cursor of the last post on the previous page
+    after: Option<String>,
+}
 
-        if let Some(session_id) = cookies.get(name) {
-            return Some(session_id.clone());
-        } else {
-            return None;
+#[derive(Serialize)]
+struct TagsTemplate {
+    tags: Vec<TagCount>,
+}
+
+/// The posts an index page lists.
+#[derive(Debug, Clone)]
+enum Listing {
+    Published,
+    Tagged(Tag),
+}
+
+impl Listing {
+    fn template(&self) -> &'static str {
+        match self {
+            Self::Published => "index.html",
+            Self::Tagged(_) => "tag.html",
         }
-    } else {
-        return None;
+    }
+
+    fn page_url(&self, number: u32) -> String {
+        match (self, number) {
+            (Self::Published, 1) => String::from("/"),
+            (Self::Published, number) => format!("/page/{}", number),
+            (Self::Tagged(tag), 1) => format!("/tags/{}", tag),
+            (Self::Tagged(tag), number) => format!("/tags/{}/page/{}", tag, number),
+        }
+    }
+
+    async fn fetch(
+        &self,
+        state: &AppState,
+        after: Option<PublishedCursor>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<Post>, ApiError> {
+        match self {
+            Self::Published => state.post_store.list_published(after, offset, limit).await,
+            Self::Tagged(tag) => {
+                state
+                    .post_store
+                    .list_tagged(tag.clone(), after, offset, limit)
+                    .await
+            }
+        }
+        .map_err(|_| ApiError::Internal)
     }
 }
 
+#[derive(Serialize)]
+struct PasswordTemplate {
+    id: String,
+}
+
+#[derive(Serialize)]
+struct InvalidPostTemplate {
+    message: String,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 struct LoginRequest {
     id: String,
     password: String,
 }
 
+async fn record_login_failure(state: &AppState, ip: IpAddr, post_id: Option<PostId>) {
+    let limiter = &state.attempt_limiter;
+    limiter.record_failure(AttemptKey::Ip(ip));
+
+    let Some(post_id) = post_id else {
+        return;
+    };
+
+    limiter.record_failure(AttemptKey::Post(post_id.clone()));
+
+    let limits = limiter.limits();
+    if let Some(threshold) = limits.lockout_threshold {
+        match state
+            .lockout_store
+            .record_failure(
+                post_id.clone(),
+                threshold,
+                limits.window,
+                limits.lockout_cooldown,
+            )
+            .await
+        {
+            Ok(Some(_)) => println!("post {post_id} locked after {threshold} failed login(s)"),
+            Ok(None) => {}
+            Err(error) => eprintln!("failed to record login failure for {post_id}: {error}"),
+        }
+    }
+}
+
 async fn handle_login(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
+    jar: CookieJar,
     State(state): State<AppState>,
     Json(request): Json<LoginRequest>,
 ) -> Response {
-    let session_id: Option<SessionId> =
-        if let Some(session_id) = extract_cookie(headers, "session_id") {
-            Some(session_id.into())
-        } else {
-            None
-        };
+    let ip = forwarded::client_ip(addr.ip(), &headers, &state.trusted_proxies);
+    if let Some(retry_after) = state.attempt_limiter.retry_after(&AttemptKey::Ip(ip)) {
+        return ApiError::TooManyRequests(retry_after).into_response();
+    }
+
+    let session_id = state.session_cookies.session_id(&jar);
+
+    let post = match state.post_store.get_by_id(request.id.clone().into()).await {
+        Ok(post) => post,
+        Err(_error) => {
+            record_login_failure(&state, ip, None).await;
+            return (StatusCode::UNAUTHORIZED, "Post not found").into_response();
+        }
+    };
+
+    match state.lockout_store.locked_until(post.id.clone()).await {
+        Ok(Some(locked_until)) => {
+            let retry_after = Duration::from_secs((locked_until - unix_now()).max(0) as u64);
+            return ApiError::TooManyRequests(retry_after).into_response();
+        }
+        Ok(None) => {}
+        Err(error) => eprintln!("failed to check lockout of post {}: {}", post.id, error),
+    }
+
+    let post_key = AttemptKey::Post(post.id.clone());
+    if let Some(retry_after) = state.attempt_limiter.retry_after(&post_key) {
+        return ApiError::TooManyRequests(retry_after).into_response();
+    }
 
-    match state.post_store.get_by_id(request.id.clone().into()).await {
-        Ok(post) => {
-            if post
-                .password
-                .is_some_and(|password| password == request.password)
+    let Some(stored) = post.password.clone() else {
+        return (StatusCode::UNAUTHORIZED, "Invalid password").into_response();
+    };
+
+    let candidate = request.password.clone();
+    let verification =
+        tokio::task::spawn_blocking(move || password::verify(&stored, &candidate)).await;
+
+    match verification {
+        Ok(Verification::Valid) => {}
+        Ok(Verification::ValidLegacy) => {
+            if let Err(error) = state
+                .post_store
+                .update_password(post.id.clone(), Some(request.password))
+                .await
             {
-                match state.session_store.create(session_id, post.id.into()) {
-                    Ok(session_id) => (
-                        StatusCode::OK,
-                        AppendHeaders([
-                            ("hx-refresh", "true"),
-                            (
-                                "set-cookie",
-                                format!(
-                                    "session_id={}; HttpOnly; Secure; Path=/",
-                                    session_id.to_string()
-                                )
-                                .as_str(),
-                            ),
-                        ]),
-                        "Login successful",
-                    )
-                        .into_response(),
-                    Err(error) => (
-                        StatusCode::UNAUTHORIZED,
-                        format!("failed to create session: {}", error),
-                    )
-                        .into_response(),
-                }
-            } else {
-                (StatusCode::UNAUTHORIZED, "Invalid password").into_response()
+                eprintln!(
+                    "failed to rehash legacy password of post {}: {}",
+                    post.id, error
+                );
+            }
+        }
+        Ok(Verification::Invalid) | Err(_) => {
+            record_login_failure(&state, ip, Some(post.id)).await;
+            return (StatusCode::UNAUTHORIZED, "Invalid password").into_response();
+        }
+    }
+
+    match state.session_store.create(session_id, post.id).await {
+        Ok(session_id) => (
+            StatusCode::OK,
+            jar.add(state.session_cookies.build(&session_id)),
+            "Login successful",
+        )
+            .into_response(),
+        Err(error) => (
+            StatusCode::UNAUTHORIZED,
+            format!("failed to create session: {}", error),
+        )
+            .into_response(),
+    }
+}
+
+/// Public posts are always readable, protected ones only through a session
+/// that unlocked them.
+async fn is_unlocked(state: &AppState, post: &Post, jar: &CookieJar) -> bool {
+    if post.password.is_none() {
+        return true;
+    }
+
+    let Some(session_id) = state.session_cookies.session_id(jar) else {
+        return false;
+    };
+
+    match state
+        .session_store
+        .authorized(session_id, post.id.clone())
+        .await
+    {
+        Ok(authorized) => authorized,
+        Err(error) => {
+            eprintln!("failed to check session: {}", error);
+            false
+        }
+    }
+}
+
+pub fn render_page(
+    state: &AppState,
+    name: &str,
+    context: impl Serialize,
+) -> Result<Html<String>, ApiError> {
+    state
+        .theme
+        .render(name, context)
+        .map(Html)
+        .map_err(|error| {
+            eprintln!("failed to render {}: {:#}", name, error);
+            ApiError::Internal
+        })
+}
+
+fn invalid_post(state: &AppState, message: String) -> Result<Html<String>, ApiError> {
+    render_page(state, "invalid-post.html", InvalidPostTemplate { message })
+}
+
+/// Renders with the template named in the front matter, or `post.html`.
+async fn render_post(
+    state: &AppState,
+    post: Post,
+    front_matter: FrontMatter,
+    markdown: &str,
+    revision: Option<i64>,
+) -> Result<Html<String>, ApiError> {
+    let name = match &front_matter.template {
+        Some(template) => format!("{}.html", template),
+        None => String::from("post.html"),
+    };
+    if !state.theme.has_template(&name) {
+        return invalid_post(state, format!("unknown template {}", name));
+    }
+
+    let tags = state
+        .post_store
+        .get_tags(post.id.clone())
+        .await
+        .map_err(|_| ApiError::Internal)?;
+    let template = PostTemplate::new(
+        &state.renderer,
+        post,
+        front_matter,
+        tags,
+        markdown,
+        revision,
+    );
+    render_page(state, &name, template)
+}
+
+/// Counts a read of the post. Returns `false` if another reader used up the
+/// last view or the post expired since it was loaded.
+async fn record_view(state: &AppState, post: &Post) -> bool {
+    match state.post_store.record_view(post.id.clone()).await {
+        Ok(allowed) => allowed,
+        Err(error) => {
+            eprintln!("failed to record view of post {}: {}", post.id, error);
+            false
+        }
+    }
+}
+
+async fn handle_highlight_css(State(state): State<AppState>) -> Response {
+    match state.renderer.stylesheet() {
+        Some(stylesheet) => (
+            [(header::CONTENT_TYPE, "text/css; charset=utf-8")],
+            stylesheet.to_string(),
+        )
+            .into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+async fn handle_asset(Path(path): Path<String>) -> Response {
+    match assets::find(&path) {
+        Some(asset) => (
+            [
+                (header::CONTENT_TYPE, asset.content_type),
+                (header::CACHE_CONTROL, "public, max-age=86400"),
+            ],
+            asset.body,
+        )
+            .into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+async fn handle_index(State(state): State<AppState>) -> Result<Html<String>, ApiError> {
+    render_index(&state, Listing::Published, 1, None).await
+}
+
+async fn handle_index_page(
+    State(state): State<AppState>,
+    Path(number): Path<u32>,
+    Query(query): Query<IndexQuery>,
+) -> Result<Html<String>, ApiError> {
+    render_index_page(&state, Listing::Published, number, query).await
+}
+
+async fn handle_tags(State(state): State<AppState>) -> Result<Html<String>, ApiError> {
+    let tags = state
+        .post_store
+        .list_tags()
+        .await
+        .map_err(|_| ApiError::Internal)?;
+
+    render_page(&state, "tags.html", TagsTemplate { tags })
+}
+
+async fn handle_tag(
+    State(state): State<AppState>,
+    Path(tag): Path<String>,
+) -> Result<Html<String>, ApiError> {
+    let Some(tag) = Tag::parse(&tag) else {
+        return render_page(&state, "not-found.html", context! {});
+    };
+
+    render_index(&state, Listing::Tagged(tag), 1, None).await
+}
+
+async fn handle_tag_page(
+    State(state): State<AppState>,
+    Path((tag, number)): Path<(String, u32)>,
+    Query(query): Query<IndexQuery>,
+) -> Result<Html<String>, ApiError> {
+    let Some(tag) = Tag::parse(&tag) else {
+        return render_page(&state, "not-found.html", context! {});
+    };
+
+    render_index_page(&state, Listing::Tagged(tag), number, query).await
+}
+
+/// Pages link to the next one with a cursor. Without one, as when a page
+/// number is typed in, the posts before the page are walked to find it.
+async fn render_index_page(
+    state: &AppState,
+    listing: Listing,
+    number: u32,
+    query: IndexQuery,
+) -> Result<Html<String>, ApiError> {
+    if number == 0 {
+        return render_page(state, "not-found.html", context! {});
+    }
+
+    let after = match query.after {
+        Some(after) => match after.parse() {
+            Ok(cursor) => Some(cursor),
+            Err(()) => return Err(ApiError::BadRequest("invalid page cursor".to_string())),
+        },
+        None if number == 1 => None,
+        None => {
+            // Pages are cursor-based, so start after the last post of the
+            // previous page; the post after it shows the page is not empty.
+            let skipped = (number - 1).saturating_mul(state.posts_per_page);
+            let posts = listing.fetch(state, None, skipped - 1, 2).await?;
+            match posts.as_slice() {
+                [last, _] => Some(PublishedCursor::new(last)),
+                _ => return render_page(state, "not-found.html", context! {}),
             }
         }
-        Err(_error) => (StatusCode::UNAUTHORIZED, "Post not found").into_response(),
+    };
+
+    render_index(state, listing, number, after).await
+}
+
+async fn render_index(
+    state: &AppState,
+    listing: Listing,
+    number: u32,
+    after: Option<PublishedCursor>,
+) -> Result<Html<String>, ApiError> {
+    let per_page = state.posts_per_page;
+    let mut posts = listing
+        .fetch(state, after, 0, per_page.saturating_add(1))
+        .await?;
+
+    let next_page = if posts.len() > per_page as usize {
+        posts.truncate(per_page as usize);
+        posts.last().map(|post| {
+            format!(
+                "{}?after={}",
+                listing.page_url(number + 1),
+                PublishedCursor::new(post)
+            )
+        })
+    } else {
+        None
+    };
+    let previous_page = (number > 1).then(|| listing.page_url(number - 1));
+
+    let mut summaries = Vec::with_capacity(posts.len());
+    for post in posts {
+        let Ok(content) = state.post_store.get_content(post.id.clone()).await else {
+            continue;
+        };
+        let Ok((front_matter, markdown)) = front_matter::parse(&content) else {
+            continue;
+        };
+
+        summaries.push(PostSummary::new(
+            &state.renderer,
+            post,
+            front_matter,
+            markdown,
+        ));
     }
+
+    let tag = match &listing {
+        Listing::Published => None,
+        Listing::Tagged(tag) => Some(tag.clone()),
+    };
+    render_page(
+        state,
+        listing.template(),
+        IndexTemplate {
+            tag,
+            posts: summaries,
+            previous_page,
+            next_page,
+        },
+    )
 }
 
 async fn handle_post(
     State(state): State<AppState>,
     Path(id): Path<String>,
-    headers: HeaderMap,
+    jar: CookieJar,
 ) -> Result<Html<String>, ApiError> {
-    return match state.post_store.as_ref().get_by_id(id.clone().into()).await {
-        Ok(post) => {
-            if post.password.is_none() {
-                let path = format!("posts/{}.md", post.id);
-                return match fs::read_to_string(&path) {
-                    Ok(content) => {
-                        let content = comrak::markdown_to_html(
-                            &content,
-                            &Options {
-                                extension: ExtensionOptions {
-                                    table: true,
-                                    autolink: true,
-                                    header_ids: Some(String::new()),
-                                    wikilinks_title_after_pipe: true,
-                                    spoiler: true,
-                                    ..Default::default()
-                                },
-                                render: RenderOptions {
-                                    gfm_quirks: true,
-                                    tasklist_classes: true,
-                                    ..Default::default()
-                                },
-                                ..Default::default()
-                            },
-                        );
-                        let template = PostTemplate {
-                            id: post.id.into(),
-                            content,
-                            with_password: true,
-                        };
-
-                        Ok(Html(template.render().unwrap()))
-                    }
-                    Err(_) => Err(ApiError::PostNotFound),
-                };
-            } else {
-                let session_id: Option<SessionId> =
-                    if let Some(session_id) = extract_cookie(headers, "session_id") {
-                        Some(session_id.into())
-                    } else {
-                        None
-                    };
-
-                if session_id.is_none()
-                    || !state
-                        .session_store
-                        .authorized(session_id.unwrap(), id.clone().into())
-                        .unwrap()
-                {
-                    return Ok(Html(PasswordTemplate { id }.render().unwrap()));
-                }
-
-                let path = format!("posts/{}.md", post.id);
-                return match fs::read_to_string(&path) {
-                    Ok(content) => {
-                        let content = comrak::markdown_to_html(&content, &Options::default());
-                        let template = PostTemplate {
-                            id: post.id.into(),
-                            content,
-                            with_password: false,
-                        };
-
-                        Ok(Html(template.render().unwrap()))
-                    }
-                    Err(_) => Err(ApiError::PostNotFound),
-                };
-            }
+    let post = match state.post_store.get_by_id(id.clone().into()).await {
+        Ok(post) => post,
+        Err(error) => {
+            eprintln!("{}", error);
+            return render_page(&state, "not-found.html", context! {});
         }
+    };
+
+    if post.is_expired(unix_now()) {
+        return render_page(&state, "expired.html", context! {});
+    }
+
+    if !is_unlocked(&state, &post, &jar).await {
+        return render_page(&state, "password.html", PasswordTemplate { id });
+    }
+
+    let content = state
+        .post_store
+        .get_content(post.id.clone())
+        .await
+        .map_err(|_| ApiError::PostNotFound)?;
+    let (front_matter, markdown) = match front_matter::parse(&content) {
+        Ok(parsed) => parsed,
+        Err(error) => return invalid_post(&state, error.to_string()),
+    };
+    if front_matter.draft {
+        return render_page(&state, "not-found.html", context! {});
+    }
+
+    if !record_view(&state, &post).await {
+        return render_page(&state, "expired.html", context! {});
+    }
+
+    render_post(&state, post, front_matter, markdown, None).await
+}
+
+async fn handle_revision(
+    State(state): State<AppState>,
+    Path((id, number)): Path<(String, i64)>,
+    jar: CookieJar,
+) -> Result<Html<String>, ApiError> {
+    let post = match state.post_store.get_by_id(id.clone().into()).await {
+        Ok(post) => post,
         Err(error) => {
             eprintln!("{}", error);
-            Ok(Html(NotFoundTemplate.render().unwrap()))
+            return render_page(&state, "not-found.html", context! {});
         }
     };
+
+    if post.is_expired(unix_now()) {
+        return render_page(&state, "expired.html", context! {});
+    }
+
+    if !is_unlocked(&state, &post, &jar).await {
+        return render_page(&state, "password.html", PasswordTemplate { id });
+    }
+
+    let revision = match state.post_store.get_revision(post.id.clone(), number).await {
+        Ok(revision) => revision,
+        Err(_) => return render_page(&state, "not-found.html", context! {}),
+    };
+    let (front_matter, markdown) = match front_matter::parse(&revision.content) {
+        Ok(parsed) => parsed,
+        Err(error) => return invalid_post(&state, error.to_string()),
+    };
+    if front_matter.draft {
+        return render_page(&state, "not-found.html", context! {});
+    }
+
+    if !record_view(&state, &post).await {
+        return render_page(&state, "expired.html", context! {});
+    }
+
+    render_post(&state, post, front_matter, markdown, Some(revision.number)).await
 }
@@ -1,4 +1,12 @@
+pub mod api;
+pub mod assets;
+pub mod auth;
 pub mod error;
+pub mod feed;
+pub mod forwarded;
 pub mod http;
 pub mod params;
+pub mod search;
+pub mod sitemap;
 pub mod state;
+pub mod tls;
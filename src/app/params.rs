@@ -1,10 +1,79 @@
+use std::net::IpAddr;
 use std::path::PathBuf;
+use std::time::Duration;
+
+use axum::http::HeaderValue;
+use clap::ValueEnum;
+
+use crate::limit::entity::AttemptLimits;
+use crate::render;
+use crate::render::config::{HighlightConfig, HighlightStyle, MarkdownConfig, MathMode};
+use crate::session::entity::SessionExpiry;
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, ValueEnum)]
+pub enum SessionStoreKind {
+    /// Sessions are lost when the process exits
+    #[default]
+    Memory,
+    /// Sessions are kept in the `sessions` and `session_posts` tables
+    Sqlite,
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, ValueEnum)]
+pub enum CookieSameSite {
+    Strict,
+    #[default]
+    Lax,
+    None,
+}
+
+/// PEM files the HTTPS listener serves.
+#[derive(Debug, Clone)]
+pub struct TlsFiles {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
 
 #[derive(Debug, Clone)]
 pub struct HttpParams {
     pub host: String,
     pub port: u16,
+    /// Serve HTTPS instead of plain HTTP
+    pub tls: Option<TlsFiles>,
+    /// Plain HTTP port redirecting to the HTTPS one
+    pub http_redirect_port: Option<u16>,
     pub static_path: PathBuf,
+    /// Templates here replace the embedded ones of the same name
+    pub template_path: PathBuf,
+    /// Reload templates when they change
+    pub dev: bool,
+    pub session_store: SessionStoreKind,
+    pub session_expiry: SessionExpiry,
+    pub session_gc_interval: Duration,
+    /// Signs session cookies; a random key is generated when unset, which the
+    /// sqlite session store does not allow
+    pub session_key: Option<String>,
+    /// Keys accepted when verifying cookies but no longer used for signing
+    pub previous_session_keys: Vec<String>,
+    pub cookie_same_site: CookieSameSite,
+    pub cookie_domain: Option<String>,
+    /// Mark the session cookie `Secure`; only with `tls` when unset
+    pub cookie_secure: Option<bool>,
+    /// Proxies whose `X-Forwarded-For` names the client for login limits
+    pub trusted_proxies: Vec<IpAddr>,
+    pub attempt_limits: AttemptLimits,
+    pub post_sweep_interval: Duration,
+    /// Posts on each page of the index
+    pub posts_per_page: u32,
+    /// Prefix of absolute links in feeds; `http://{host}:{port}` when unset
+    pub base_url: Option<String>,
+    /// Served at `/robots.txt` instead of the default
+    pub robots_txt: Option<String>,
+    pub markdown: MarkdownConfig,
+    pub highlight: HighlightConfig,
+    pub math: MathMode,
+    /// Sent as `Content-Security-Policy` on every response
+    pub content_security_policy: String,
 }
 
 impl Default for HttpParams {
@@ -12,7 +81,29 @@ impl Default for HttpParams {
         Self {
             host: String::from("127.0.0.1"),
             port: 2201,
+            tls: None,
+            http_redirect_port: None,
             static_path: PathBuf::from("static"),
+            template_path: PathBuf::from("templates"),
+            dev: false,
+            session_store: SessionStoreKind::default(),
+            session_expiry: SessionExpiry::default(),
+            session_gc_interval: Duration::from_secs(10 * 60),
+            session_key: None,
+            previous_session_keys: Vec::new(),
+            cookie_same_site: CookieSameSite::default(),
+            cookie_domain: None,
+            cookie_secure: None,
+            trusted_proxies: Vec::new(),
+            attempt_limits: AttemptLimits::default(),
+            post_sweep_interval: Duration::from_secs(60),
+            posts_per_page: 10,
+            base_url: None,
+            robots_txt: None,
+            markdown: MarkdownConfig::default(),
+            highlight: HighlightConfig::default(),
+            math: MathMode::default(),
+            content_security_policy: String::from(DEFAULT_CONTENT_SECURITY_POLICY),
         }
     }
 }
@@ -25,8 +116,26 @@ pub struct HttpParamsBuilder {
 pub enum HttpParamsError {
     InvalidHost,
     InvalidPort,
+    RedirectWithoutTls,
+    ZeroDuration,
+    WeakSessionKey,
+    SqliteSessionsWithoutKey,
+    InvalidCookieDomain,
+    ZeroAttempts,
+    ZeroPostsPerPage,
+    InvalidBaseUrl,
+    UnknownHighlightTheme,
+    InvalidContentSecurityPolicy,
 }
 
+const MIN_SESSION_KEY_LEN: usize = 32;
+
+/// Only same-origin scripts, styles, fonts and images; inline styles are
+/// allowed for the templates and inline-highlighted code.
+pub const DEFAULT_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; script-src 'self'; \
+    style-src 'self' 'unsafe-inline'; font-src 'self'; img-src 'self' data:; \
+    object-src 'none'; base-uri 'none'; form-action 'self'; frame-ancestors 'none'";
+
 impl HttpParamsBuilder {
     pub fn new() -> Self {
         Self {
@@ -52,12 +161,259 @@ impl HttpParamsBuilder {
         Ok(self)
     }
 
+    pub fn tls(&mut self, cert: PathBuf, key: PathBuf) -> Result<&mut Self, HttpParamsError> {
+        self.params.tls = Some(TlsFiles { cert, key });
+        Ok(self)
+    }
+
+    pub fn http_redirect_port(&mut self, port: u16) -> Result<&mut Self, HttpParamsError> {
+        if port == 0 {
+            return Err(HttpParamsError::InvalidPort);
+        }
+
+        self.params.http_redirect_port = Some(port);
+        Ok(self)
+    }
+
     pub fn static_path(&mut self, path: PathBuf) -> Result<&mut Self, HttpParamsError> {
         self.params.static_path = path;
         Ok(self)
     }
 
+    pub fn template_path(&mut self, path: PathBuf) -> Result<&mut Self, HttpParamsError> {
+        self.params.template_path = path;
+        Ok(self)
+    }
+
+    pub fn dev(&mut self, dev: bool) -> Result<&mut Self, HttpParamsError> {
+        self.params.dev = dev;
+        Ok(self)
+    }
+
+    pub fn session_store(&mut self, kind: SessionStoreKind) -> Result<&mut Self, HttpParamsError> {
+        self.params.session_store = kind;
+        Ok(self)
+    }
+
+    pub fn session_lifetime(&mut self, lifetime: Duration) -> Result<&mut Self, HttpParamsError> {
+        if lifetime.is_zero() {
+            return Err(HttpParamsError::ZeroDuration);
+        }
+
+        self.params.session_expiry.lifetime = lifetime;
+        Ok(self)
+    }
+
+    pub fn session_idle_timeout(
+        &mut self,
+        idle_timeout: Duration,
+    ) -> Result<&mut Self, HttpParamsError> {
+        if idle_timeout.is_zero() {
+            return Err(HttpParamsError::ZeroDuration);
+        }
+
+        self.params.session_expiry.idle_timeout = idle_timeout;
+        Ok(self)
+    }
+
+    pub fn session_gc_interval(
+        &mut self,
+        interval: Duration,
+    ) -> Result<&mut Self, HttpParamsError> {
+        if interval.is_zero() {
+            return Err(HttpParamsError::ZeroDuration);
+        }
+
+        self.params.session_gc_interval = interval;
+        Ok(self)
+    }
+
+    pub fn post_sweep_interval(
+        &mut self,
+        interval: Duration,
+    ) -> Result<&mut Self, HttpParamsError> {
+        if interval.is_zero() {
+            return Err(HttpParamsError::ZeroDuration);
+        }
+
+        self.params.post_sweep_interval = interval;
+        Ok(self)
+    }
+
+    pub fn posts_per_page(&mut self, posts: u32) -> Result<&mut Self, HttpParamsError> {
+        if posts == 0 {
+            return Err(HttpParamsError::ZeroPostsPerPage);
+        }
+
+        self.params.posts_per_page = posts;
+        Ok(self)
+    }
+
+    pub fn base_url(&mut self, url: String) -> Result<&mut Self, HttpParamsError> {
+        let url = url.trim_end_matches('/');
+        let host = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"));
+        if host.is_none_or(|host| host.is_empty() || host.contains(char::is_whitespace)) {
+            return Err(HttpParamsError::InvalidBaseUrl);
+        }
+
+        self.params.base_url = Some(url.to_string());
+        Ok(self)
+    }
+
+    pub fn robots_txt(&mut self, robots_txt: String) -> Result<&mut Self, HttpParamsError> {
+        self.params.robots_txt = Some(robots_txt);
+        Ok(self)
+    }
+
+    pub fn markdown(&mut self, config: MarkdownConfig) -> Result<&mut Self, HttpParamsError> {
+        self.params.markdown = config;
+        Ok(self)
+    }
+
+    pub fn highlight_theme(&mut self, theme: String) -> Result<&mut Self, HttpParamsError> {
+        if !render::is_known_theme(&theme) {
+            return Err(HttpParamsError::UnknownHighlightTheme);
+        }
+
+        self.params.highlight.theme = theme;
+        Ok(self)
+    }
+
+    pub fn highlight_style(&mut self, style: HighlightStyle) -> Result<&mut Self, HttpParamsError> {
+        self.params.highlight.style = style;
+        Ok(self)
+    }
+
+    pub fn math(&mut self, mode: MathMode) -> Result<&mut Self, HttpParamsError> {
+        self.params.math = mode;
+        Ok(self)
+    }
+
+    pub fn content_security_policy(
+        &mut self,
+        policy: String,
+    ) -> Result<&mut Self, HttpParamsError> {
+        if policy.is_empty() || HeaderValue::from_str(&policy).is_err() {
+            return Err(HttpParamsError::InvalidContentSecurityPolicy);
+        }
+
+        self.params.content_security_policy = policy;
+        Ok(self)
+    }
+
+    pub fn session_key(&mut self, key: String) -> Result<&mut Self, HttpParamsError> {
+        if key.len() < MIN_SESSION_KEY_LEN {
+            return Err(HttpParamsError::WeakSessionKey);
+        }
+
+        self.params.session_key = Some(key);
+        Ok(self)
+    }
+
+    pub fn previous_session_keys(
+        &mut self,
+        keys: Vec<String>,
+    ) -> Result<&mut Self, HttpParamsError> {
+        if keys.iter().any(|key| key.len() < MIN_SESSION_KEY_LEN) {
+            return Err(HttpParamsError::WeakSessionKey);
+        }
+
+        self.params.previous_session_keys = keys;
+        Ok(self)
+    }
+
+    pub fn cookie_same_site(
+        &mut self,
+        same_site: CookieSameSite,
+    ) -> Result<&mut Self, HttpParamsError> {
+        self.params.cookie_same_site = same_site;
+        Ok(self)
+    }
+
+    pub fn cookie_domain(&mut self, domain: String) -> Result<&mut Self, HttpParamsError> {
+        if domain.is_empty() || domain.contains([';', ' ', '=']) {
+            return Err(HttpParamsError::InvalidCookieDomain);
+        }
+
+        self.params.cookie_domain = Some(domain);
+        Ok(self)
+    }
+
+    pub fn cookie_secure(&mut self, secure: bool) -> Result<&mut Self, HttpParamsError> {
+        self.params.cookie_secure = Some(secure);
+        Ok(self)
+    }
+
+    pub fn trusted_proxies(&mut self, proxies: Vec<IpAddr>) -> Result<&mut Self, HttpParamsError> {
+        self.params.trusted_proxies = proxies;
+        Ok(self)
+    }
+
+    pub fn login_window(&mut self, window: Duration) -> Result<&mut Self, HttpParamsError> {
+        if window.is_zero() {
+            return Err(HttpParamsError::ZeroDuration);
+        }
+
+        self.params.attempt_limits.window = window;
+        Ok(self)
+    }
+
+    pub fn max_failures_per_ip(&mut self, max: u32) -> Result<&mut Self, HttpParamsError> {
+        if max == 0 {
+            return Err(HttpParamsError::ZeroAttempts);
+        }
+
+        self.params.attempt_limits.max_failures_per_ip = max;
+        Ok(self)
+    }
+
+    pub fn max_failures_per_post(&mut self, max: u32) -> Result<&mut Self, HttpParamsError> {
+        if max == 0 {
+            return Err(HttpParamsError::ZeroAttempts);
+        }
+
+        self.params.attempt_limits.max_failures_per_post = max;
+        Ok(self)
+    }
+
+    pub fn lockout_threshold(&mut self, threshold: u32) -> Result<&mut Self, HttpParamsError> {
+        if threshold == 0 {
+            return Err(HttpParamsError::ZeroAttempts);
+        }
+
+        self.params.attempt_limits.lockout_threshold = Some(threshold);
+        Ok(self)
+    }
+
+    pub fn lockout_cooldown(&mut self, cooldown: Duration) -> Result<&mut Self, HttpParamsError> {
+        if cooldown.is_zero() {
+            return Err(HttpParamsError::ZeroDuration);
+        }
+
+        self.params.attempt_limits.lockout_cooldown = cooldown;
+        Ok(self)
+    }
+
     pub fn build(&self) -> Result<HttpParams, HttpParamsError> {
+        if let Some(port) = self.params.http_redirect_port {
+            if self.params.tls.is_none() {
+                return Err(HttpParamsError::RedirectWithoutTls);
+            }
+
+            if port == self.params.port {
+                return Err(HttpParamsError::InvalidPort);
+            }
+        }
+
+        // A random key would invalidate every stored session on restart.
+        if self.params.session_store == SessionStoreKind::Sqlite
+            && self.params.session_key.is_none()
+        {
+            return Err(HttpParamsError::SqliteSessionsWithoutKey);
+        }
+
         Ok(self.params.clone())
     }
 }
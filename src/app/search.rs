@@ -0,0 +1,133 @@
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    response::Html,
+    routing::get,
+};
+use axum_extra::extract::cookie::CookieJar;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{
+        error::ApiError,
+        http::{post_title, render_page},
+        state::AppState,
+    },
+    clock::format_date,
+    post::{front_matter, search::highlight_html, store::PostStore},
+};
+
+const DEFAULT_RESULTS: u32 = 20;
+const MAX_RESULTS: u32 = 100;
+
+/// Both routes read the session cookie, so protected posts the reader has
+/// unlocked show up too.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/search", get(handle_search_page))
+        .route("/api/search", get(handle_search_api))
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchQuery {
+    #[serde(default)]
+    q: String,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct SearchResult {
+    id: String,
+    url: String,
+    title: String,
+    date: String,
+    published_at: i64,
+    /// Escaped text around the matches, which are wrapped in `<mark>`
+    snippet: String,
+}
+
+#[derive(Serialize)]
+struct SearchTemplate {
+    query: String,
+    results: Vec<SearchResult>,
+}
+
+#[derive(Debug, Serialize)]
+struct SearchResponse {
+    results: Vec<SearchResult>,
+}
+
+async fn search(
+    state: &AppState,
+    jar: &CookieJar,
+    query: &str,
+    limit: u32,
+) -> Result<Vec<SearchResult>, ApiError> {
+    let unlocked = match state.session_cookies.session_id(jar) {
+        Some(session_id) => state
+            .session_store
+            .authorized_posts(session_id)
+            .await
+            .unwrap_or_else(|error| {
+                eprintln!("failed to check session: {}", error);
+                Vec::new()
+            }),
+        None => Vec::new(),
+    };
+
+    let hits = state
+        .post_store
+        .search(query, unlocked, limit)
+        .await
+        .map_err(|_| ApiError::Internal)?;
+
+    let mut results = Vec::with_capacity(hits.len());
+    for hit in hits {
+        let post = hit.post;
+        let Ok(content) = state.post_store.get_content(post.id.clone()).await else {
+            continue;
+        };
+        let Ok((front_matter, markdown)) = front_matter::parse(&content) else {
+            continue;
+        };
+
+        results.push(SearchResult {
+            title: post_title(&state.renderer, &post, &front_matter, markdown),
+            url: format!("/{}", post.id),
+            date: format_date(post.published_at),
+            published_at: post.published_at,
+            snippet: highlight_html(&hit.snippet),
+            id: post.id.into(),
+        });
+    }
+
+    Ok(results)
+}
+
+async fn handle_search_page(
+    State(state): State<AppState>,
+    Query(query): Query<SearchQuery>,
+    jar: CookieJar,
+) -> Result<Html<String>, ApiError> {
+    let results = search(&state, &jar, &query.q, DEFAULT_RESULTS).await?;
+
+    render_page(
+        &state,
+        "search.html",
+        SearchTemplate {
+            query: query.q,
+            results,
+        },
+    )
+}
+
+async fn handle_search_api(
+    State(state): State<AppState>,
+    Query(query): Query<SearchQuery>,
+    jar: CookieJar,
+) -> Result<Json<SearchResponse>, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_RESULTS).clamp(1, MAX_RESULTS);
+    let results = search(&state, &jar, &query.q, limit).await?;
+
+    Ok(Json(SearchResponse { results }))
+}
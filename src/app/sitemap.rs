@@ -0,0 +1,88 @@
+use axum::{
+    Router,
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+
+use crate::{
+    app::{error::ApiError, state::AppState},
+    clock::format_unix_time,
+    post::store::PostStore,
+};
+
+/// The most URLs a single sitemap may list.
+const SITEMAP_LEN: u32 = 50_000;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/sitemap.xml", get(handle_sitemap))
+        .route("/robots.txt", get(handle_robots_txt))
+}
+
+/// Served at `/robots.txt` unless replaced with `--robots-txt`.
+pub fn default_robots_txt(base_url: &str) -> String {
+    format!(
+        "User-agent: *\nDisallow: /api/\nDisallow: /search\n\nSitemap: {}/sitemap.xml\n",
+        base_url
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn push_url(sitemap: &mut String, loc: &str, lastmod: Option<i64>) {
+    sitemap.push_str("<url><loc>");
+    sitemap.push_str(&escape_xml(loc));
+    sitemap.push_str("</loc>");
+    if let Some(lastmod) = lastmod {
+        sitemap.push_str("<lastmod>");
+        sitemap.push_str(&format_unix_time(lastmod));
+        sitemap.push_str("</lastmod>");
+    }
+    sitemap.push_str("</url>\n");
+}
+
+/// The index and every published post without `noindex`.
+async fn handle_sitemap(State(state): State<AppState>) -> Result<Response, ApiError> {
+    let posts = state
+        .post_store
+        .list_indexable(SITEMAP_LEN - 1)
+        .await
+        .map_err(|_| ApiError::Internal)?;
+
+    let mut sitemap = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+        <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    let index_lastmod = posts.iter().map(|post| post.updated_at).max();
+    push_url(&mut sitemap, &format!("{}/", state.base_url), index_lastmod);
+    for post in posts {
+        push_url(
+            &mut sitemap,
+            &format!("{}/{}", state.base_url, post.id),
+            Some(post.updated_at),
+        );
+    }
+    sitemap.push_str("</urlset>\n");
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+        sitemap,
+    )
+        .into_response())
+}
+
+async fn handle_robots_txt(State(state): State<AppState>) -> Response {
+    (
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        state.robots_txt.to_string(),
+    )
+        .into_response()
+}
@@ -1,21 +1,66 @@
+use std::net::IpAddr;
 use std::sync::Arc;
 
 use crate::{
+    limit::{limiter::AttemptLimiter, store::SqliteLockoutStore},
     post::store::SqlitePostStore,
-    session::store::{MemorySessionStore, SessionStore},
+    render::Renderer,
+    session::{cookie::SessionCookies, store::SessionStore},
+    theme::Theme,
+    token::store::SqliteTokenStore,
 };
 
 #[derive(Clone)]
 pub struct AppState {
     pub post_store: Arc<SqlitePostStore>,
     pub session_store: Arc<dyn SessionStore + Send + Sync>,
+    pub session_cookies: Arc<SessionCookies>,
+    pub attempt_limiter: Arc<AttemptLimiter>,
+    pub lockout_store: Arc<SqliteLockoutStore>,
+    /// Proxies whose `X-Forwarded-For` names the client for login limits
+    pub trusted_proxies: Arc<[IpAddr]>,
+    pub token_store: Arc<SqliteTokenStore>,
+    pub renderer: Arc<Renderer>,
+    pub theme: Arc<Theme>,
+    /// Posts on each page of the index
+    pub posts_per_page: u32,
+    /// Scheme and host that absolute links start with, without a trailing slash
+    pub base_url: String,
+    /// Served at `/robots.txt`
+    pub robots_txt: Arc<str>,
+}
+
+/// What [`AppState`] is built from.
+pub struct AppStateParams {
+    pub post_store: Arc<SqlitePostStore>,
+    pub session_store: Arc<dyn SessionStore + Send + Sync>,
+    pub session_cookies: SessionCookies,
+    pub attempt_limiter: AttemptLimiter,
+    pub lockout_store: SqliteLockoutStore,
+    pub trusted_proxies: Vec<IpAddr>,
+    pub token_store: SqliteTokenStore,
+    pub renderer: Renderer,
+    pub theme: Arc<Theme>,
+    pub posts_per_page: u32,
+    pub base_url: String,
+    pub robots_txt: String,
 }
 
 impl AppState {
-    pub fn new(post_store: SqlitePostStore, session_store: MemorySessionStore) -> Self {
+    pub fn new(params: AppStateParams) -> Self {
         Self {
-            post_store: Arc::new(post_store),
-            session_store: Arc::new(session_store),
+            post_store: params.post_store,
+            session_store: params.session_store,
+            session_cookies: Arc::new(params.session_cookies),
+            attempt_limiter: Arc::new(params.attempt_limiter),
+            lockout_store: Arc::new(params.lockout_store),
+            trusted_proxies: Arc::from(params.trusted_proxies),
+            token_store: Arc::new(params.token_store),
+            renderer: Arc::new(params.renderer),
+            theme: params.theme,
+            posts_per_page: params.posts_per_page,
+            base_url: params.base_url,
+            robots_txt: Arc::from(params.robots_txt),
         }
     }
 }
@@ -0,0 +1,129 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use axum::{
+    Router,
+    http::{HeaderMap, Uri, header, uri::Authority},
+    response::Redirect,
+};
+use axum_server::tls_rustls::RustlsConfig;
+use notify::{EventKind, RecursiveMode, Watcher};
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Certificate renewals write the certificate and key one after the other;
+/// waiting this long after a change avoids loading a mismatched pair.
+const RELOAD_DELAY: Duration = Duration::from_secs(1);
+
+/// Reads a PEM certificate chain and private key.
+pub async fn load_config(cert: &Path, key: &Path) -> io::Result<RustlsConfig> {
+    // Several providers may be compiled in through dependencies; an already
+    // installed default is fine.
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    RustlsConfig::from_pem_file(cert, key).await
+}
+
+/// Reloads the certificate and key on SIGHUP and whenever either file
+/// changes. Open connections keep the configuration they started with; a pair
+/// that fails to load is logged and the previous one stays in use.
+pub fn spawn_reloader(config: RustlsConfig, cert: PathBuf, key: PathBuf) -> JoinHandle<()> {
+    let (sender, mut changes) = mpsc::unbounded_channel();
+    let watcher = watch_files(&[cert.clone(), key.clone()], sender)
+        .inspect_err(|error| eprintln!("failed to watch tls files for changes: {}", error))
+        .ok();
+
+    tokio::spawn(async move {
+        // Dropping the watcher would stop the events.
+        let _watcher = watcher;
+
+        let mut hangups = match signal(SignalKind::hangup()) {
+            Ok(hangups) => Some(hangups),
+            Err(error) => {
+                eprintln!("failed to listen for SIGHUP: {}", error);
+                None
+            }
+        };
+
+        loop {
+            tokio::select! {
+                Some(()) = async { hangups.as_mut()?.recv().await } => {}
+                Some(()) = changes.recv() => {
+                    tokio::time::sleep(RELOAD_DELAY).await;
+                    while changes.try_recv().is_ok() {}
+                }
+                else => return,
+            }
+
+            match config.reload_from_pem_file(&cert, &key).await {
+                Ok(()) => println!("reloaded tls certificate from {}", cert.display()),
+                Err(error) => eprintln!("failed to reload tls certificate: {}", error),
+            }
+        }
+    })
+}
+
+/// Watches the directories holding `files`, since renewals often replace a
+/// file or the symlink to it rather than writing to it.
+fn watch_files(
+    files: &[PathBuf],
+    sender: mpsc::UnboundedSender<()>,
+) -> notify::Result<notify::RecommendedWatcher> {
+    let names: Vec<_> = files
+        .iter()
+        .filter_map(|file| file.file_name().map(|name| name.to_os_string()))
+        .collect();
+
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+
+        let touches_files = event.paths.iter().any(|path| {
+            path.file_name()
+                .is_some_and(|name| names.iter().any(|n| n == name))
+        });
+        if touches_files {
+            let _ = sender.send(());
+        }
+    })?;
+
+    let mut directories: Vec<&Path> = files
+        .iter()
+        .map(|file| match file.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        })
+        .collect();
+    directories.dedup();
+    for directory in directories {
+        watcher.watch(directory, RecursiveMode::NonRecursive)?;
+    }
+
+    Ok(watcher)
+}
+
+/// Answers every plain HTTP request with a permanent redirect to the same
+/// path on the HTTPS listener at `https_port`.
+pub fn redirect_router(host: String, https_port: u16) -> Router {
+    Router::new().fallback(move |headers: HeaderMap, uri: Uri| async move {
+        let host = headers
+            .get(header::HOST)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<Authority>().ok())
+            .map(|authority| authority.host().to_string())
+            .unwrap_or(host);
+        let port = match https_port {
+            443 => String::new(),
+            port => format!(":{}", port),
+        };
+        let path = uri.path_and_query().map_or("/", |path| path.as_str());
+
+        Redirect::permanent(&format!("https://{}{}{}", host, port, path))
+    })
+}
@@ -0,0 +1,20 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Seconds since the unix epoch, as stored in the database.
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Formats a stored timestamp as RFC 3339, e.g. `2025-01-31T12:00:00Z`.
+pub fn format_unix_time(secs: i64) -> String {
+    let time = UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64);
+    humantime::format_rfc3339_seconds(time).to_string()
+}
+
+/// Formats a stored timestamp as a calendar date, e.g. `2025-01-31`.
+pub fn format_date(secs: i64) -> String {
+    format_unix_time(secs)[..10].to_string()
+}
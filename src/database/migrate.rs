@@ -0,0 +1,412 @@
+use sqlx::SqlitePool;
+use sqlx::migrate::{MigrateError, Migrator};
+use thiserror::Error;
+
+use crate::post::entity::PostId;
+use crate::post::front_matter;
+use crate::post::store::{index_post, sync_front_matter_tags};
+
+static MIGRATOR: Migrator = sqlx::migrate!();
+
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    #[error("migration failed: {0}")]
+    Migrate(#[from] MigrateError),
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+}
+
+/// Lists every embedded migration and whether the database has applied it.
+/// Does not write to the database.
+pub async fn status(pool: &SqlitePool) -> Result<Vec<MigrationStatus>, MigrationError> {
+    let applied: Vec<i64> = if is_tracked(pool).await? {
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+            .fetch_all(pool)
+            .await?
+    } else {
+        Vec::new()
+    };
+
+    Ok(MIGRATOR
+        .iter()
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            description: migration.description.to_string(),
+            applied: applied.contains(&migration.version),
+        })
+        .collect())
+}
+
+/// The migrations adding the columns that mirror post front matter.
+const POST_LISTING_VERSION: i64 = 3;
+const POST_NOINDEX_VERSION: i64 = 6;
+
+/// The migration adding the tag tables, filled from post front matter.
+const TAGS_VERSION: i64 = 4;
+
+/// The migration adding the full-text search index.
+const SEARCH_VERSION: i64 = 5;
+
+/// Applies pending migrations and returns the ones that ran.
+pub async fn run(pool: &SqlitePool) -> Result<Vec<MigrationStatus>, MigrationError> {
+    let pending: Vec<MigrationStatus> = status(pool)
+        .await?
+        .into_iter()
+        .filter(|migration| !migration.applied)
+        .collect();
+
+    if pending.is_empty() {
+        return Ok(pending);
+    }
+
+    adopt_legacy_schema(pool).await?;
+    MIGRATOR.run(pool).await?;
+
+    if pending.iter().any(|migration| {
+        migration.version == POST_LISTING_VERSION || migration.version == POST_NOINDEX_VERSION
+    }) {
+        backfill_front_matter(pool).await?;
+    }
+
+    if pending
+        .iter()
+        .any(|migration| migration.version == TAGS_VERSION)
+    {
+        backfill_tags(pool).await?;
+    }
+
+    if pending
+        .iter()
+        .any(|migration| migration.version == SEARCH_VERSION)
+    {
+        backfill_search(pool).await?;
+    }
+
+    Ok(pending)
+}
+
+async fn is_tracked(pool: &SqlitePool) -> Result<bool, sqlx::Error> {
+    table_exists(pool, "_sqlx_migrations").await
+}
+
+async fn table_exists(pool: &SqlitePool, table: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar("SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = $1")
+        .bind(table)
+        .fetch_one(pool)
+        .await
+}
+
+/// Databases from before migrations existed created their tables on startup
+/// and grew `posts` column by column. Adds whatever columns such a database is
+/// missing so the migrations can take over from there.
+async fn adopt_legacy_schema(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    if is_tracked(pool).await? || !table_exists(pool, "posts").await? {
+        return Ok(());
+    }
+
+    add_column_if_missing(pool, "content", "TEXT NOT NULL DEFAULT ''").await?;
+    add_column_if_missing(pool, "expires_at", "INTEGER").await?;
+    add_column_if_missing(pool, "max_views", "INTEGER").await?;
+    add_column_if_missing(pool, "views", "INTEGER NOT NULL DEFAULT 0").await?;
+    make_password_nullable(pool).await?;
+
+    Ok(())
+}
+
+/// Legacy databases declared `password NOT NULL`, which rejects public posts.
+/// SQLite cannot alter a column constraint, so the table is rebuilt. Dropping
+/// it cascades into `post_revisions`, whose rows are set aside and restored.
+async fn make_password_nullable(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let not_null: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('posts')
+        WHERE name = 'password' AND \"notnull\"",
+    )
+    .fetch_one(pool)
+    .await?;
+    if !not_null {
+        return Ok(());
+    }
+
+    let has_revisions = table_exists(pool, "post_revisions").await?;
+    let mut transaction = pool.begin().await?;
+
+    if has_revisions {
+        sqlx::query(
+            "CREATE TEMPORARY TABLE post_revisions_backup AS
+            SELECT post_id, number, content, created_at, author FROM post_revisions",
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+
+    sqlx::raw_sql(
+        "CREATE TABLE posts_new (
+            id TEXT PRIMARY KEY,
+            password TEXT,
+            content TEXT NOT NULL DEFAULT '',
+            expires_at INTEGER,
+            max_views INTEGER,
+            views INTEGER NOT NULL DEFAULT 0
+        );
+        INSERT INTO posts_new (id, password, content, expires_at, max_views, views)
+        SELECT id, password, content, expires_at, max_views, views FROM posts;
+        DROP TABLE posts;
+        ALTER TABLE posts_new RENAME TO posts;",
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    if has_revisions {
+        sqlx::raw_sql(
+            "INSERT INTO post_revisions (post_id, number, content, created_at, author)
+            SELECT post_id, number, content, created_at, author FROM post_revisions_backup;
+            DROP TABLE post_revisions_backup;",
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+
+    transaction.commit().await
+}
+
+/// SQL cannot read front matter out of the content, so the columns mirroring
+/// it are filled in here for posts that existed before they were added.
+async fn backfill_front_matter(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let posts: Vec<(String, String)> = sqlx::query_as("SELECT id, content FROM posts")
+        .fetch_all(pool)
+        .await?;
+
+    for (id, content) in posts {
+        let Ok((front_matter, _)) = front_matter::parse(&content) else {
+            continue;
+        };
+
+        sqlx::query(
+            "UPDATE posts SET published_at = COALESCE($1, created_at), draft = $2, noindex = $3
+            WHERE id = $4",
+        )
+        .bind(front_matter.date)
+        .bind(front_matter.draft)
+        .bind(front_matter.noindex)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn backfill_tags(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let posts: Vec<(PostId, String)> = sqlx::query_as("SELECT id, content FROM posts")
+        .fetch_all(pool)
+        .await?;
+
+    let mut transaction = pool.begin().await?;
+    for (id, content) in posts {
+        let Ok((front_matter, _)) = front_matter::parse(&content) else {
+            continue;
+        };
+
+        sync_front_matter_tags(&mut transaction, &id, &front_matter.tags).await?;
+    }
+
+    transaction.commit().await
+}
+
+async fn backfill_search(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let ids: Vec<PostId> = sqlx::query_scalar("SELECT id FROM posts")
+        .fetch_all(pool)
+        .await?;
+
+    let mut transaction = pool.begin().await?;
+    for id in ids {
+        index_post(&mut transaction, &id).await?;
+    }
+
+    transaction.commit().await
+}
+
+async fn add_column_if_missing(
+    pool: &SqlitePool,
+    column: &str,
+    definition: &str,
+) -> Result<(), sqlx::Error> {
+    let exists: bool =
+        sqlx::query_scalar("SELECT COUNT(*) > 0 FROM pragma_table_info('posts') WHERE name = $1")
+            .bind(column)
+            .fetch_one(pool)
+            .await?;
+
+    if !exists {
+        sqlx::query(&format!(
+            "ALTER TABLE posts ADD COLUMN {column} {definition}"
+        ))
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::unix_now;
+    use crate::database;
+
+    async fn execute(pool: &SqlitePool, sql: &str) {
+        sqlx::raw_sql(sql).execute(pool).await.unwrap();
+    }
+
+    async fn password_not_null(pool: &SqlitePool) -> bool {
+        sqlx::query_scalar(
+            "SELECT \"notnull\" FROM pragma_table_info('posts') WHERE name = 'password'",
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn creates_fresh_database() {
+        let pool = database::connect_memory().await;
+
+        let applied = run(&pool).await.unwrap();
+
+        assert_eq!(applied.len(), MIGRATOR.iter().count());
+        assert!(!password_not_null(&pool).await);
+        assert!(status(&pool).await.unwrap().iter().all(|m| m.applied));
+        assert!(run(&pool).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn adopts_baseline_schema() {
+        let pool = database::connect_memory().await;
+        execute(
+            &pool,
+            "CREATE TABLE posts (id TEXT PRIMARY KEY, password TEXT NOT NULL);
+            INSERT INTO posts (id, password) VALUES ('plain', 'hunter2');",
+        )
+        .await;
+
+        run(&pool).await.unwrap();
+
+        assert!(!password_not_null(&pool).await);
+        let posts: Vec<(String, Option<String>, String)> =
+            sqlx::query_as("SELECT id, password, content FROM posts")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            posts,
+            [(
+                "plain".to_string(),
+                Some("hunter2".to_string()),
+                String::new()
+            )]
+        );
+
+        execute(
+            &pool,
+            "INSERT INTO posts (id, password) VALUES ('public', NULL)",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn keeps_revisions_of_legacy_posts() {
+        let pool = database::connect_memory().await;
+        execute(
+            &pool,
+            "CREATE TABLE posts (
+                id TEXT PRIMARY KEY,
+                password TEXT NOT NULL,
+                content TEXT NOT NULL DEFAULT '',
+                expires_at INTEGER,
+                max_views INTEGER,
+                views INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE TABLE post_revisions (
+                post_id TEXT NOT NULL REFERENCES posts (id) ON DELETE CASCADE,
+                number INTEGER NOT NULL,
+                content TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                author TEXT,
+                PRIMARY KEY (post_id, number)
+            );
+            INSERT INTO posts (id, password, content, views) VALUES ('notes', 'pw', 'second', 3);
+            INSERT INTO post_revisions (post_id, number, content, created_at, author)
+            VALUES ('notes', 1, 'first', 100, 'cli'), ('notes', 2, 'second', 200, NULL);",
+        )
+        .await;
+
+        run(&pool).await.unwrap();
+
+        let post: (String, String, i64) =
+            sqlx::query_as("SELECT password, content, views FROM posts WHERE id = 'notes'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(post, ("pw".to_string(), "second".to_string(), 3));
+
+        let revisions: Vec<(i64, String, i64, Option<String>)> = sqlx::query_as(
+            "SELECT number, content, created_at, author FROM post_revisions ORDER BY number",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            revisions,
+            [
+                (1, "first".to_string(), 100, Some("cli".to_string())),
+                (2, "second".to_string(), 200, None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn backfills_post_timestamps() {
+        let pool = database::connect_memory().await;
+        execute(
+            &pool,
+            "CREATE TABLE posts (id TEXT PRIMARY KEY, password TEXT NOT NULL);
+            CREATE TABLE post_revisions (
+                post_id TEXT NOT NULL REFERENCES posts (id) ON DELETE CASCADE,
+                number INTEGER NOT NULL,
+                content TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                author TEXT,
+                PRIMARY KEY (post_id, number)
+            );
+            INSERT INTO posts (id, password) VALUES ('old', 'pw'), ('revised', 'pw');
+            INSERT INTO post_revisions (post_id, number, content, created_at)
+            VALUES ('revised', 1, 'a', 100), ('revised', 2, 'b', 200);",
+        )
+        .await;
+
+        let before = unix_now();
+        run(&pool).await.unwrap();
+        let after = unix_now();
+
+        let timestamps: Vec<(String, i64, i64, i64)> = sqlx::query_as(
+            "SELECT id, created_at, updated_at, published_at FROM posts ORDER BY id",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+
+        let (_, created_at, updated_at, published_at) = &timestamps[0];
+        assert!((before..=after).contains(created_at));
+        assert!((before..=after).contains(updated_at));
+        assert_eq!(published_at, created_at);
+
+        assert_eq!(timestamps[1], ("revised".to_string(), 100, 200, 100));
+    }
+}
@@ -0,0 +1,38 @@
+use sqlx::SqlitePool;
+use sqlx::sqlite::SqlitePoolOptions;
+
+pub mod migrate;
+
+/// Opens the database named by `DATABASE_URL`, falling back to an in-memory one.
+pub async fn connect() -> Result<SqlitePool, sqlx::Error> {
+    let database_url =
+        std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite::memory:".to_string());
+
+    SqlitePoolOptions::new()
+        .max_connections(5)
+        .connect(&database_url)
+        .await
+}
+
+/// An empty in-memory database; the single connection keeps it alive.
+#[cfg(test)]
+pub async fn connect_memory() -> SqlitePool {
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await
+        .expect("failed to open database")
+}
+
+/// [`connect_memory`] with every migration applied.
+#[cfg(test)]
+pub async fn connect_migrated() -> SqlitePool {
+    let pool = connect_memory().await;
+    migrate::run(&pool)
+        .await
+        .expect("failed to migrate database");
+
+    pool
+}
@@ -3,12 +3,14 @@ use std::path::PathBuf;
 #[derive(Debug, Clone)]
 pub struct InitParams {
     pub static_path: PathBuf,
+    pub template_path: PathBuf,
 }
 
 impl Default for InitParams {
     fn default() -> Self {
         Self {
             static_path: PathBuf::from("static"),
+            template_path: PathBuf::from("templates"),
         }
     }
 }
@@ -32,6 +34,11 @@ impl InitParamsBuilder {
         Ok(self)
     }
 
+    pub fn template_path(&mut self, path: PathBuf) -> Result<&mut Self, InitParamsError> {
+        self.params.template_path = path;
+        Ok(self)
+    }
+
     pub fn build(&self) -> Result<InitParams, InitParamsError> {
         Ok(self.params.clone())
     }
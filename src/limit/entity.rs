@@ -0,0 +1,53 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
+use sqlx::FromRow;
+
+use crate::post::entity::PostId;
+
+/// What failed login attempts are counted against.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum AttemptKey {
+    Ip(IpAddr),
+    Post(PostId),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AttemptLimits {
+    /// Sliding window in which failures are counted
+    pub window: Duration,
+    pub max_failures_per_ip: u32,
+    pub max_failures_per_post: u32,
+    /// Failures within `window` after which a post is locked, if set
+    pub lockout_threshold: Option<u32>,
+    pub lockout_cooldown: Duration,
+}
+
+impl AttemptLimits {
+    pub fn max_failures(&self, key: &AttemptKey) -> u32 {
+        match key {
+            AttemptKey::Ip(_) => self.max_failures_per_ip,
+            AttemptKey::Post(_) => self.max_failures_per_post,
+        }
+    }
+}
+
+impl Default for AttemptLimits {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(15 * 60),
+            max_failures_per_ip: 10,
+            max_failures_per_post: 30,
+            lockout_threshold: None,
+            lockout_cooldown: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct PostLockout {
+    pub post_id: PostId,
+    pub failures: i64,
+    pub last_failure_at: i64,
+    pub locked_until: Option<i64>,
+}
@@ -0,0 +1,143 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::limit::entity::{AttemptKey, AttemptLimits};
+
+/// Counts failed logins per key in a sliding window. State lives in memory
+/// only; persistent per-post lockouts are kept by the lockout store.
+pub struct AttemptLimiter {
+    limits: AttemptLimits,
+    failures: Mutex<HashMap<AttemptKey, VecDeque<Instant>>>,
+}
+
+impl AttemptLimiter {
+    pub fn new(limits: AttemptLimits) -> Self {
+        Self {
+            limits,
+            failures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn limits(&self) -> &AttemptLimits {
+        &self.limits
+    }
+
+    /// Returns how long the caller has to wait if `key` has used up its failures.
+    pub fn retry_after(&self, key: &AttemptKey) -> Option<Duration> {
+        let mut failures = self.failures.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+
+        let attempts = failures.get_mut(key)?;
+        self.forget_stale(attempts, now);
+
+        let max_failures = self.limits.max_failures(key) as usize;
+        if attempts.len() < max_failures {
+            return None;
+        }
+
+        let oldest = attempts.front()?;
+        Some(self.limits.window.saturating_sub(now - *oldest))
+    }
+
+    pub fn record_failure(&self, key: AttemptKey) {
+        let mut failures = self.failures.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+
+        failures.retain(|_, attempts| {
+            self.forget_stale(attempts, now);
+            !attempts.is_empty()
+        });
+        failures.entry(key).or_default().push_back(now);
+    }
+
+    fn forget_stale(&self, attempts: &mut VecDeque<Instant>, now: Instant) {
+        while attempts
+            .front()
+            .is_some_and(|attempt| now - *attempt >= self.limits.window)
+        {
+            attempts.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::thread::sleep;
+
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_millis(200);
+
+    fn limiter() -> AttemptLimiter {
+        AttemptLimiter::new(AttemptLimits {
+            window: WINDOW,
+            max_failures_per_ip: 2,
+            max_failures_per_post: 3,
+            ..AttemptLimits::default()
+        })
+    }
+
+    fn ip(last: u8) -> AttemptKey {
+        AttemptKey::Ip(IpAddr::V4(Ipv4Addr::new(192, 0, 2, last)))
+    }
+
+    #[test]
+    fn blocks_once_the_failures_are_used_up() {
+        let limiter = limiter();
+
+        limiter.record_failure(ip(1));
+        assert_eq!(limiter.retry_after(&ip(1)), None);
+
+        limiter.record_failure(ip(1));
+        let retry_after = limiter.retry_after(&ip(1)).unwrap();
+        assert!(!retry_after.is_zero() && retry_after <= WINDOW);
+    }
+
+    #[test]
+    fn counts_each_key_separately() {
+        let limiter = limiter();
+        let post = AttemptKey::Post("notes".into());
+
+        limiter.record_failure(ip(1));
+        limiter.record_failure(ip(1));
+        limiter.record_failure(post.clone());
+        limiter.record_failure(post.clone());
+
+        assert!(limiter.retry_after(&ip(1)).is_some());
+        assert_eq!(limiter.retry_after(&ip(2)), None);
+        assert_eq!(limiter.retry_after(&post), None);
+
+        limiter.record_failure(post.clone());
+        assert!(limiter.retry_after(&post).is_some());
+    }
+
+    #[test]
+    fn forgets_failures_once_the_window_has_passed() {
+        let limiter = limiter();
+
+        limiter.record_failure(ip(1));
+        limiter.record_failure(ip(1));
+        assert!(limiter.retry_after(&ip(1)).is_some());
+
+        sleep(WINDOW);
+        assert_eq!(limiter.retry_after(&ip(1)), None);
+
+        limiter.record_failure(ip(1));
+        assert_eq!(limiter.retry_after(&ip(1)), None);
+    }
+
+    #[test]
+    fn slides_the_window_past_the_oldest_failure() {
+        let limiter = limiter();
+
+        limiter.record_failure(ip(1));
+        sleep(WINDOW / 2);
+        limiter.record_failure(ip(1));
+        assert!(limiter.retry_after(&ip(1)).unwrap() <= WINDOW / 2);
+
+        sleep(WINDOW / 2);
+        assert_eq!(limiter.retry_after(&ip(1)), None);
+    }
+}
@@ -0,0 +1,220 @@
+use std::time::Duration;
+
+use sqlx::SqlitePool;
+use thiserror::Error;
+
+use crate::clock::unix_now;
+use crate::limit::entity::PostLockout;
+use crate::post::entity::PostId;
+
+#[derive(Debug, Error)]
+pub enum LockoutStoreError {
+    #[error("database error: {0}")]
+    Database(String),
+}
+
+impl From<sqlx::Error> for LockoutStoreError {
+    fn from(error: sqlx::Error) -> Self {
+        Self::Database(error.to_string())
+    }
+}
+
+pub trait LockoutStore {
+    /// Returns the unix time until which the post is locked, if it is locked now.
+    async fn locked_until(&self, post_id: PostId) -> Result<Option<i64>, LockoutStoreError>;
+    /// Counts a failed login and locks the post once `threshold` failures
+    /// accumulate within `window`. Returns the new lock expiry, if any.
+    async fn record_failure(
+        &self,
+        post_id: PostId,
+        threshold: u32,
+        window: Duration,
+        cooldown: Duration,
+    ) -> Result<Option<i64>, LockoutStoreError>;
+    async fn list(&self) -> Result<Vec<PostLockout>, LockoutStoreError>;
+    /// Clears the given post, or every post when `None`. Returns how many rows were removed.
+    async fn clear(&self, post_id: Option<PostId>) -> Result<u64, LockoutStoreError>;
+}
+
+pub struct SqliteLockoutStore {
+    pool: SqlitePool,
+}
+
+impl SqliteLockoutStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+impl LockoutStore for SqliteLockoutStore {
+    async fn locked_until(&self, post_id: PostId) -> Result<Option<i64>, LockoutStoreError> {
+        let row: Option<(i64,)> = sqlx::query_as(
+            "SELECT locked_until FROM post_lockouts WHERE post_id = $1 AND locked_until > $2",
+        )
+        .bind(post_id)
+        .bind(unix_now())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|(locked_until,)| locked_until))
+    }
+
+    async fn record_failure(
+        &self,
+        post_id: PostId,
+        threshold: u32,
+        window: Duration,
+        cooldown: Duration,
+    ) -> Result<Option<i64>, LockoutStoreError> {
+        let now = unix_now();
+        let mut transaction = self.pool.begin().await?;
+
+        let (failures,): (i64,) = sqlx::query_as(
+            "INSERT INTO post_lockouts (post_id, failures, window_start, last_failure_at)
+            VALUES ($1, 1, $2, $2)
+            ON CONFLICT (post_id) DO UPDATE SET
+                failures = CASE WHEN window_start <= $3 THEN 1 ELSE failures + 1 END,
+                window_start = CASE WHEN window_start <= $3 THEN $2 ELSE window_start END,
+                last_failure_at = excluded.last_failure_at
+            RETURNING failures",
+        )
+        .bind(post_id.clone())
+        .bind(now)
+        .bind(now - window.as_secs() as i64)
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        let locked_until = if failures >= threshold as i64 {
+            let locked_until = now + cooldown.as_secs() as i64;
+            sqlx::query(
+                "UPDATE post_lockouts SET failures = 0, locked_until = $1 WHERE post_id = $2",
+            )
+            .bind(locked_until)
+            .bind(post_id)
+            .execute(&mut *transaction)
+            .await?;
+            Some(locked_until)
+        } else {
+            None
+        };
+
+        transaction.commit().await?;
+        Ok(locked_until)
+    }
+
+    async fn list(&self) -> Result<Vec<PostLockout>, LockoutStoreError> {
+        let lockouts = sqlx::query_as(
+            "SELECT post_id, failures, last_failure_at, locked_until FROM post_lockouts
+            ORDER BY locked_until DESC, last_failure_at DESC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(lockouts)
+    }
+
+    async fn clear(&self, post_id: Option<PostId>) -> Result<u64, LockoutStoreError> {
+        let result = match post_id {
+            Some(post_id) => {
+                sqlx::query("DELETE FROM post_lockouts WHERE post_id = $1")
+                    .bind(post_id)
+                    .execute(&self.pool)
+                    .await?
+            }
+            None => {
+                sqlx::query("DELETE FROM post_lockouts")
+                    .execute(&self.pool)
+                    .await?
+            }
+        };
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database;
+
+    const WINDOW: Duration = Duration::from_secs(100);
+    const COOLDOWN: Duration = Duration::from_secs(60);
+
+    async fn store() -> SqliteLockoutStore {
+        SqliteLockoutStore::new(database::connect_migrated().await)
+    }
+
+    async fn fail(store: &SqliteLockoutStore, post_id: &str) -> Option<i64> {
+        store
+            .record_failure(PostId::new(post_id), 3, WINDOW, COOLDOWN)
+            .await
+            .unwrap()
+    }
+
+    /// Moves every recorded failure `seconds` into the past.
+    async fn rewind(store: &SqliteLockoutStore, seconds: i64) {
+        sqlx::query(
+            "UPDATE post_lockouts SET
+                window_start = window_start - $1, last_failure_at = last_failure_at - $1",
+        )
+        .bind(seconds)
+        .execute(&store.pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn locks_after_threshold_within_window() {
+        let store = store().await;
+
+        assert_eq!(fail(&store, "a").await, None);
+        assert_eq!(fail(&store, "a").await, None);
+        let locked_until = fail(&store, "a").await.expect("post should be locked");
+
+        assert!(locked_until >= unix_now() + COOLDOWN.as_secs() as i64 - 1);
+        assert_eq!(
+            store.locked_until(PostId::new("a")).await.unwrap(),
+            Some(locked_until)
+        );
+        assert_eq!(store.locked_until(PostId::new("b")).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn counts_posts_separately() {
+        let store = store().await;
+
+        fail(&store, "a").await;
+        fail(&store, "a").await;
+
+        assert_eq!(fail(&store, "b").await, None);
+    }
+
+    #[tokio::test]
+    async fn forgets_failures_after_window() {
+        let store = store().await;
+
+        fail(&store, "a").await;
+        fail(&store, "a").await;
+        rewind(&store, WINDOW.as_secs() as i64).await;
+
+        assert_eq!(fail(&store, "a").await, None);
+        assert_eq!(fail(&store, "a").await, None);
+    }
+
+    #[tokio::test]
+    async fn does_not_chain_spaced_failures() {
+        let store = store().await;
+
+        // Each failure comes just within a window of the previous one, but
+        // never three within the same window.
+        fail(&store, "a").await;
+        rewind(&store, 60).await;
+        fail(&store, "a").await;
+        rewind(&store, 60).await;
+
+        assert_eq!(fail(&store, "a").await, None);
+        rewind(&store, 60).await;
+        assert_eq!(fail(&store, "a").await, None);
+        assert!(fail(&store, "a").await.is_some());
+    }
+}
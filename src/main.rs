@@ -1,19 +1,36 @@
+use std::net::IpAddr;
 use std::path::PathBuf;
+use std::time::Duration;
 
-use clap::{Parser, Subcommand};
-use sqlx::sqlite::SqlitePoolOptions;
+use clap::{Args, Parser, Subcommand};
+use similar::TextDiff;
+use sqlx::SqlitePool;
 
 use crate::app::http;
-use crate::app::params::HttpParamsBuilder;
+use crate::app::params::{CookieSameSite, HttpParamsBuilder, SessionStoreKind};
+use crate::clock::{format_unix_time, unix_now};
+use crate::database::migrate;
 use crate::init::params::InitParamsBuilder;
 use crate::init::utils::init;
-use crate::post::entity::PostId;
-use crate::post::store::{PostStore, SqlitePostStore};
+use crate::limit::store::{LockoutStore, SqliteLockoutStore};
+use crate::post::entity::{NewPost, PostId, Tag};
+use crate::post::front_matter;
+use crate::post::search;
+use crate::post::store::{PostStore, PostStoreError, SqlitePostStore};
+use crate::render::config::{HighlightStyle, MarkdownConfig, MathMode};
+use crate::token::entity::{Scope, TokenId};
+use crate::token::store::{SqliteTokenStore, TokenStore};
 
 mod app;
+mod clock;
+mod database;
 mod init;
+mod limit;
 mod post;
+mod render;
 mod session;
+mod theme;
+mod token;
 
 #[derive(Parser)]
 pub struct Cli {
@@ -23,19 +40,13 @@ pub struct Cli {
 
 #[derive(Subcommand)]
 pub enum Commands {
-    Run {
-        #[arg(long)]
-        host: Option<String>,
-
-        #[arg(long)]
-        port: Option<u16>,
-
-        #[arg(long)]
-        static_path: Option<String>,
-    },
+    Run(Box<RunArgs>),
     Init {
         #[arg(long)]
         static_path: Option<String>,
+
+        #[arg(long)]
+        template_path: Option<String>,
     },
     Create {
         #[arg(long)]
@@ -43,22 +54,322 @@ pub enum Commands {
 
         #[arg(long)]
         password: Option<String>,
+
+        /// Markdown file with the post content
+        #[arg(long)]
+        file: Option<PathBuf>,
+
+        /// Defaults to the first heading of the content
+        #[arg(long)]
+        title: Option<String>,
+
+        #[arg(long)]
+        description: Option<String>,
+
+        /// Byline shown on the post page
+        #[arg(long)]
+        author: Option<String>,
+
+        /// Delete the post after this long, e.g. `24h`
+        #[arg(long, value_parser = humantime::parse_duration)]
+        expires_in: Option<Duration>,
+
+        /// Delete the post after this many reads
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        max_views: Option<u32>,
+
+        /// Delete the post after its first read; same as `--max-views 1`
+        #[arg(long, conflicts_with = "max_views")]
+        burn_after_read: bool,
+
+        /// Keep the post off the index; it is still served to anyone with the link
+        #[arg(long)]
+        unlisted: bool,
+
+        /// Tag the post, in addition to the tags in its front matter; may be repeated
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<Tag>,
     },
     Delete {
         #[arg(long)]
         id: PostId,
     },
+    /// Apply pending database migrations
+    Migrate {
+        /// Only list the migrations that would be applied
+        #[arg(long, conflicts_with = "status")]
+        dry_run: bool,
+
+        /// List every migration and whether it has been applied
+        #[arg(long)]
+        status: bool,
+    },
+    /// Replace every plaintext post password in the database with an Argon2 hash
+    Rehash,
+    /// Move `<dir>/<id>.md` files written by older versions into the database
+    ImportFiles {
+        #[arg(long, default_value = "posts")]
+        dir: PathBuf,
+
+        /// Also create public posts for files that have no row yet
+        #[arg(long)]
+        create_missing: bool,
+    },
+    /// Print a unified diff between two revisions of a post
+    Diff {
+        id: PostId,
+        rev_a: i64,
+        rev_b: i64,
+    },
+    /// Search the content of published posts, best match first; protected
+    /// posts are left out
+    Search {
+        query: String,
+
+        #[arg(long, default_value_t = 20)]
+        limit: u32,
+    },
+    /// Inspect or clear posts locked after repeated failed logins
+    Lockout {
+        #[command(subcommand)]
+        command: LockoutCommands,
+    },
+    /// Manage bearer tokens for the management API
+    Token {
+        #[command(subcommand)]
+        command: TokenCommands,
+    },
+}
+
+#[derive(Args)]
+pub struct RunArgs {
+    #[arg(long)]
+    host: Option<String>,
+
+    #[arg(long)]
+    port: Option<u16>,
+
+    #[arg(long)]
+    static_path: Option<String>,
+
+    /// Directory of templates overriding the built-in ones
+    #[arg(long, env = "POSTER_TEMPLATE_PATH")]
+    template_path: Option<String>,
+
+    /// Reload templates as soon as they change
+    #[arg(long)]
+    dev: bool,
+
+    /// Where unlocked-post sessions are kept
+    #[arg(long, value_enum)]
+    session_store: Option<SessionStoreKind>,
+
+    /// Absolute session lifetime, e.g. `7d`
+    #[arg(long, value_parser = humantime::parse_duration)]
+    session_lifetime: Option<Duration>,
+
+    /// Session expires after this long without requests, e.g. `24h`
+    #[arg(long, value_parser = humantime::parse_duration)]
+    session_idle_timeout: Option<Duration>,
+
+    /// How often expired sessions are evicted, e.g. `10m`
+    #[arg(long, value_parser = humantime::parse_duration)]
+    session_gc_interval: Option<Duration>,
+
+    /// Key used to sign session cookies, at least 32 bytes
+    #[arg(long, env = "POSTER_SESSION_KEY", hide_env_values = true)]
+    session_key: Option<String>,
+
+    /// Former signing keys still accepted during rotation
+    #[arg(
+        long,
+        env = "POSTER_PREVIOUS_SESSION_KEYS",
+        value_delimiter = ',',
+        hide_env_values = true
+    )]
+    previous_session_key: Vec<String>,
+
+    #[arg(long, value_enum)]
+    cookie_same_site: Option<CookieSameSite>,
+
+    #[arg(long)]
+    cookie_domain: Option<String>,
+
+    /// Mark the session cookie `Secure`, e.g. behind a TLS-terminating proxy;
+    /// on by default only with --tls-cert
+    #[arg(long, env = "POSTER_COOKIE_SECURE")]
+    cookie_secure: Option<bool>,
+
+    /// Address of a reverse proxy whose X-Forwarded-For header names the
+    /// client that failed logins are counted against; may be repeated
+    #[arg(long, env = "POSTER_TRUSTED_PROXIES", value_delimiter = ',')]
+    trusted_proxy: Vec<IpAddr>,
+
+    /// Window in which failed logins are counted, e.g. `15m`
+    #[arg(long, value_parser = humantime::parse_duration)]
+    login_window: Option<Duration>,
+
+    #[arg(long)]
+    max_failures_per_ip: Option<u32>,
+
+    #[arg(long)]
+    max_failures_per_post: Option<u32>,
+
+    /// Lock a post after this many failed logins within the window
+    #[arg(long)]
+    lockout_threshold: Option<u32>,
+
+    /// How long a locked post rejects logins, e.g. `1h`
+    #[arg(long, value_parser = humantime::parse_duration)]
+    lockout_cooldown: Option<Duration>,
+
+    /// How often expired posts are deleted, e.g. `1m`
+    #[arg(long, value_parser = humantime::parse_duration)]
+    post_sweep_interval: Option<Duration>,
+
+    /// Posts on each page of the index
+    #[arg(long)]
+    posts_per_page: Option<u32>,
+
+    /// Scheme and host of the public address, e.g. https://example.com, used
+    /// for absolute links in feeds
+    #[arg(long, env = "POSTER_BASE_URL")]
+    base_url: Option<String>,
+
+    /// TOML file selecting the markdown extensions posts are rendered with
+    #[arg(long, env = "POSTER_RENDER_CONFIG")]
+    render_config: Option<PathBuf>,
+
+    /// Theme for code blocks: InspiredGitHub, Solarized (light), Solarized (dark),
+    /// base16-ocean.light, base16-ocean.dark, base16-eighties.dark or base16-mocha.dark
+    #[arg(long)]
+    highlight_theme: Option<String>,
+
+    #[arg(long, value_enum)]
+    highlight_style: Option<HighlightStyle>,
+
+    /// Where math is typeset; posts can override it with `math` in front matter
+    #[arg(long, value_enum)]
+    math: Option<MathMode>,
+
+    /// Replaces the default Content-Security-Policy header, e.g. to allow images from other hosts
+    #[arg(long, env = "POSTER_CONTENT_SECURITY_POLICY")]
+    content_security_policy: Option<String>,
+
+    /// File served at /robots.txt instead of one that only keeps crawlers
+    /// off the API and search and points them to the sitemap
+    #[arg(long, env = "POSTER_ROBOTS_TXT")]
+    robots_txt: Option<PathBuf>,
+
+    /// PEM certificate chain to serve HTTPS with; reloaded on SIGHUP or when it changes
+    #[arg(long, env = "POSTER_TLS_CERT", requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key for --tls-cert
+    #[arg(long, env = "POSTER_TLS_KEY", requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
+    /// Plain HTTP port redirecting every request to the HTTPS listener
+    #[arg(long, requires = "tls_cert")]
+    http_redirect_port: Option<u16>,
+}
+
+#[derive(Subcommand)]
+pub enum TokenCommands {
+    Create {
+        #[arg(long)]
+        name: String,
+
+        #[arg(long = "scope", value_enum, required = true)]
+        scopes: Vec<Scope>,
+    },
+    List,
+    Revoke {
+        #[arg(long)]
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum LockoutCommands {
+    List,
+    Clear {
+        #[arg(long, required_unless_present = "all")]
+        id: Option<PostId>,
+
+        #[arg(long, conflicts_with = "id")]
+        all: bool,
+    },
+}
+
+/// Connects to a database whose schema is up to date. Only `run` and
+/// `migrate` change the schema, so other commands stop here until it is.
+async fn connect_database() -> Option<SqlitePool> {
+    let pool = match database::connect().await {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("failed to connect to database: {}", e);
+            return None;
+        }
+    };
+
+    match migrate::status(&pool).await {
+        Ok(migrations) if migrations.iter().all(|migration| migration.applied) => Some(pool),
+        Ok(_) => {
+            eprintln!("database schema is out of date, run `poster migrate` first");
+            None
+        }
+        Err(error) => {
+            eprintln!("failed to read migration status: {}", error);
+            None
+        }
+    }
+}
+
+async fn connect_post_store() -> Option<SqlitePostStore> {
+    let pool = connect_database().await?;
+    Some(SqlitePostStore::new(pool))
 }
 
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
     match &cli.command {
-        Commands::Run {
-            host,
-            port,
-            static_path,
-        } => {
+        Commands::Run(args) => {
+            let RunArgs {
+                host,
+                port,
+                static_path,
+                template_path,
+                dev,
+                session_store,
+                session_lifetime,
+                session_idle_timeout,
+                session_gc_interval,
+                session_key,
+                previous_session_key,
+                cookie_same_site,
+                cookie_domain,
+                cookie_secure,
+                trusted_proxy,
+                login_window,
+                max_failures_per_ip,
+                max_failures_per_post,
+                lockout_threshold,
+                lockout_cooldown,
+                post_sweep_interval,
+                posts_per_page,
+                base_url,
+                render_config,
+                highlight_theme,
+                highlight_style,
+                math,
+                content_security_policy,
+                robots_txt,
+                tls_cert,
+                tls_key,
+                http_redirect_port,
+            } = args.as_ref();
             let mut params_builder = HttpParamsBuilder::new();
 
             if let Some(host) = host {
@@ -77,10 +388,187 @@ async fn main() {
                     .expect("failed to set static path");
             }
 
+            if let Some(template_path) = template_path {
+                params_builder
+                    .template_path(PathBuf::from(template_path))
+                    .expect("failed to set template path");
+            }
+
+            params_builder.dev(*dev).expect("failed to set dev mode");
+
+            if let Some(session_store) = session_store {
+                params_builder
+                    .session_store(*session_store)
+                    .expect("failed to set session store");
+            }
+
+            if let Some(session_lifetime) = session_lifetime {
+                params_builder
+                    .session_lifetime(*session_lifetime)
+                    .expect("failed to set session lifetime");
+            }
+
+            if let Some(session_idle_timeout) = session_idle_timeout {
+                params_builder
+                    .session_idle_timeout(*session_idle_timeout)
+                    .expect("failed to set session idle timeout");
+            }
+
+            if let Some(session_gc_interval) = session_gc_interval {
+                params_builder
+                    .session_gc_interval(*session_gc_interval)
+                    .expect("failed to set session gc interval");
+            }
+
+            if let Some(session_key) = session_key {
+                params_builder
+                    .session_key(session_key.clone())
+                    .expect("failed to set session key");
+            }
+
+            params_builder
+                .previous_session_keys(previous_session_key.clone())
+                .expect("failed to set previous session keys");
+
+            if let Some(cookie_same_site) = cookie_same_site {
+                params_builder
+                    .cookie_same_site(*cookie_same_site)
+                    .expect("failed to set cookie same site");
+            }
+
+            if let Some(cookie_domain) = cookie_domain {
+                params_builder
+                    .cookie_domain(cookie_domain.clone())
+                    .expect("failed to set cookie domain");
+            }
+
+            if let Some(cookie_secure) = cookie_secure {
+                params_builder
+                    .cookie_secure(*cookie_secure)
+                    .expect("failed to set cookie secure");
+            }
+
+            params_builder
+                .trusted_proxies(trusted_proxy.clone())
+                .expect("failed to set trusted proxies");
+
+            if let Some(login_window) = login_window {
+                params_builder
+                    .login_window(*login_window)
+                    .expect("failed to set login window");
+            }
+
+            if let Some(max_failures_per_ip) = max_failures_per_ip {
+                params_builder
+                    .max_failures_per_ip(*max_failures_per_ip)
+                    .expect("failed to set max failures per ip");
+            }
+
+            if let Some(max_failures_per_post) = max_failures_per_post {
+                params_builder
+                    .max_failures_per_post(*max_failures_per_post)
+                    .expect("failed to set max failures per post");
+            }
+
+            if let Some(lockout_threshold) = lockout_threshold {
+                params_builder
+                    .lockout_threshold(*lockout_threshold)
+                    .expect("failed to set lockout threshold");
+            }
+
+            if let Some(lockout_cooldown) = lockout_cooldown {
+                params_builder
+                    .lockout_cooldown(*lockout_cooldown)
+                    .expect("failed to set lockout cooldown");
+            }
+
+            if let Some(post_sweep_interval) = post_sweep_interval {
+                params_builder
+                    .post_sweep_interval(*post_sweep_interval)
+                    .expect("failed to set post sweep interval");
+            }
+
+            if let Some(posts_per_page) = posts_per_page {
+                params_builder
+                    .posts_per_page(*posts_per_page)
+                    .expect("failed to set posts per page");
+            }
+
+            if let Some(base_url) = base_url {
+                params_builder
+                    .base_url(base_url.to_string())
+                    .expect("failed to set base url");
+            }
+
+            if let Some(render_config) = render_config {
+                let config = match MarkdownConfig::load(render_config) {
+                    Ok(config) => config,
+                    Err(error) => {
+                        eprintln!("{}: {}", render_config.display(), error);
+                        return;
+                    }
+                };
+
+                params_builder
+                    .markdown(config)
+                    .expect("failed to set markdown config");
+            }
+
+            if let Some(highlight_theme) = highlight_theme {
+                params_builder
+                    .highlight_theme(highlight_theme.clone())
+                    .expect("failed to set highlight theme");
+            }
+
+            if let Some(highlight_style) = highlight_style {
+                params_builder
+                    .highlight_style(*highlight_style)
+                    .expect("failed to set highlight style");
+            }
+
+            if let Some(math) = math {
+                params_builder.math(*math).expect("failed to set math mode");
+            }
+
+            if let Some(content_security_policy) = content_security_policy {
+                params_builder
+                    .content_security_policy(content_security_policy.clone())
+                    .expect("failed to set content security policy");
+            }
+
+            if let Some(robots_txt) = robots_txt {
+                let content = match std::fs::read_to_string(robots_txt) {
+                    Ok(content) => content,
+                    Err(error) => {
+                        eprintln!("failed to read {}: {}", robots_txt.display(), error);
+                        return;
+                    }
+                };
+
+                params_builder
+                    .robots_txt(content)
+                    .expect("failed to set robots.txt");
+            }
+
+            if let (Some(tls_cert), Some(tls_key)) = (tls_cert, tls_key) {
+                params_builder
+                    .tls(tls_cert.clone(), tls_key.clone())
+                    .expect("failed to set tls files");
+            }
+
+            if let Some(http_redirect_port) = http_redirect_port {
+                params_builder
+                    .http_redirect_port(*http_redirect_port)
+                    .expect("failed to set http redirect port");
+            }
+
             let params = params_builder.build().expect("failed to build params");
             http::run(params).await;
         }
-        Commands::Init { static_path } => {
+        Commands::Init {
+            static_path,
+            template_path,
+        } => {
             let mut params_builder = InitParamsBuilder::new();
 
             if let Some(static_path) = static_path {
@@ -89,45 +577,240 @@ async fn main() {
                     .expect("failed to set static path");
             }
 
+            if let Some(template_path) = template_path {
+                params_builder
+                    .template_path(PathBuf::from(template_path))
+                    .expect("failed to set template path");
+            }
+
             let params = params_builder.build().expect("failed to build params");
             init(params).expect("failed to init");
         }
-        Commands::Create { id, password } => {
-            let database_url =
-                std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite::memory:".to_string());
-
-            let pool = match SqlitePoolOptions::new()
-                .max_connections(5)
-                .connect(&database_url)
-                .await
-            {
-                Ok(pool) => pool,
-                Err(e) => {
-                    eprintln!("failed to connect to database: {}", e);
+        Commands::Create {
+            id,
+            password,
+            file,
+            title,
+            description,
+            author,
+            expires_in,
+            max_views,
+            burn_after_read,
+            unlisted,
+            tags,
+        } => {
+            if let Some(id) = id.as_ref().filter(|id| !id.is_valid()) {
+                eprintln!(
+                    "id '{}' is reserved or not letters, digits, '-' and '_'",
+                    id
+                );
+                return;
+            }
+
+            let content = match file {
+                Some(file) => match std::fs::read_to_string(file) {
+                    Ok(content) => content,
+                    Err(error) => {
+                        eprintln!("failed to read {}: {}", file.display(), error);
+                        return;
+                    }
+                },
+                None => String::new(),
+            };
+
+            if let Err(error) = front_matter::parse(&content) {
+                eprintln!("{}", error);
+                return;
+            }
+
+            let Some(post_store) = connect_post_store().await else {
+                return;
+            };
+
+            let new_post = NewPost {
+                id: id.clone(),
+                password: password.clone(),
+                content,
+                expires_at: expires_in
+                    .map(|expires_in| unix_now().saturating_add_unsigned(expires_in.as_secs())),
+                max_views: if *burn_after_read {
+                    Some(1)
+                } else {
+                    max_views.map(i64::from)
+                },
+                title: title.clone(),
+                description: description.clone(),
+                author: author.clone(),
+                unlisted: *unlisted,
+                tags: tags.clone(),
+            };
+
+            match post_store.create(new_post, Some("cli".to_string())).await {
+                Ok(id) => println!("id of created post: {}", id),
+                Err(error) => eprintln!("{}", error),
+            }
+        }
+        Commands::Delete { id } => {
+            let Some(post_store) = connect_post_store().await else {
+                return;
+            };
+
+            let success = post_store.delete_by_id(id.clone()).await.unwrap();
+            if success {
+                println!("post {id} deleted successfully");
+            } else {
+                println!("failed to delete post {id}");
+            }
+        }
+        Commands::Rehash => {
+            let Some(post_store) = connect_post_store().await else {
+                return;
+            };
+
+            match post_store.rehash_passwords().await {
+                Ok(count) => println!("rehashed {count} legacy password(s)"),
+                Err(error) => eprintln!("failed to rehash passwords: {}", error),
+            }
+        }
+        Commands::ImportFiles {
+            dir,
+            create_missing,
+        } => {
+            let entries = match std::fs::read_dir(dir) {
+                Ok(entries) => entries,
+                Err(error) => {
+                    eprintln!("failed to read {}: {}", dir.display(), error);
                     return;
                 }
             };
 
-            let post_store = SqlitePostStore::new(pool)
-                .await
-                .expect("failed to create sqlite repository");
+            let Some(post_store) = connect_post_store().await else {
+                return;
+            };
+
+            let (mut imported, mut skipped) = (0, 0);
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().is_none_or(|extension| extension != "md") {
+                    continue;
+                }
+
+                let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                    continue;
+                };
+                let id = PostId::from(id);
+                if !id.is_valid() {
+                    println!("skipping {}: not a valid post id", path.display());
+                    skipped += 1;
+                    continue;
+                }
+
+                let content = match std::fs::read_to_string(&path) {
+                    Ok(content) => content,
+                    Err(error) => {
+                        eprintln!("failed to read {}: {}", path.display(), error);
+                        skipped += 1;
+                        continue;
+                    }
+                };
 
-            let id = post_store
-                .create(id.clone(), password.clone())
-                .await
-                .unwrap();
+                if let Err(error) = front_matter::parse(&content) {
+                    println!("skipping {}: {}", path.display(), error);
+                    skipped += 1;
+                    continue;
+                }
 
-            println!("id of created post: {}", id);
+                let author = Some("import-files".to_string());
+                let result = match post_store
+                    .update_content(id.clone(), content.clone(), author.clone())
+                    .await
+                {
+                    Ok(_) => Ok(()),
+                    Err(PostStoreError::PostNotFound) if *create_missing => post_store
+                        .create(
+                            NewPost {
+                                id: Some(id.clone()),
+                                content,
+                                ..NewPost::default()
+                            },
+                            author,
+                        )
+                        .await
+                        .map(|_| ()),
+                    Err(PostStoreError::PostNotFound) => {
+                        println!("skipping {}: no post with id {}", path.display(), id);
+                        skipped += 1;
+                        continue;
+                    }
+                    Err(error) => Err(error),
+                };
+
+                match result {
+                    Ok(()) => imported += 1,
+                    Err(error) => {
+                        eprintln!("failed to import {}: {}", path.display(), error);
+                        skipped += 1;
+                    }
+                }
+            }
+
+            println!("imported {imported} file(s), skipped {skipped}");
         }
-        Commands::Delete { id } => {
-            let database_url =
-                std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite::memory:".to_string());
-
-            let pool = match SqlitePoolOptions::new()
-                .max_connections(5)
-                .connect(&database_url)
-                .await
-            {
+        Commands::Diff { id, rev_a, rev_b } => {
+            let Some(post_store) = connect_post_store().await else {
+                return;
+            };
+
+            let mut revisions = Vec::with_capacity(2);
+            for number in [*rev_a, *rev_b] {
+                match post_store.get_revision(id.clone(), number).await {
+                    Ok(revision) => revisions.push(revision),
+                    Err(error) => {
+                        eprintln!("failed to load revision {number} of {id}: {}", error);
+                        return;
+                    }
+                }
+            }
+
+            let diff = TextDiff::from_lines(&revisions[0].content, &revisions[1].content);
+            print!(
+                "{}",
+                diff.unified_diff()
+                    .header(&format!("{id}@{rev_a}"), &format!("{id}@{rev_b}"))
+            );
+        }
+        Commands::Search { query, limit } => {
+            let Some(post_store) = connect_post_store().await else {
+                return;
+            };
+
+            let hits = match post_store.search(query, Vec::new(), *limit).await {
+                Ok(hits) => hits,
+                Err(error) => {
+                    eprintln!("failed to search posts: {}", error);
+                    return;
+                }
+            };
+
+            if hits.is_empty() {
+                println!("no posts match");
+            }
+
+            for hit in hits {
+                println!(
+                    "{}\t{}\t{}",
+                    hit.post.id,
+                    format_unix_time(hit.post.published_at),
+                    hit.indexed_title.as_deref().unwrap_or("-")
+                );
+                println!(
+                    "    {}",
+                    search::highlight_plain(&hit.snippet).replace('\n', " ")
+                );
+            }
+        }
+        Commands::Migrate { dry_run, status } => {
+            let pool = match database::connect().await {
                 Ok(pool) => pool,
                 Err(e) => {
                     eprintln!("failed to connect to database: {}", e);
@@ -135,15 +818,150 @@ async fn main() {
                 }
             };
 
-            let post_store = SqlitePostStore::new(pool)
-                .await
-                .expect("failed to create sqlite repository");
+            if *dry_run || *status {
+                let migrations = match migrate::status(&pool).await {
+                    Ok(migrations) => migrations,
+                    Err(error) => {
+                        eprintln!("failed to read migration status: {}", error);
+                        return;
+                    }
+                };
 
-            let success = post_store.delete_by_id(id.clone()).await.unwrap();
-            if success {
-                println!("post {id} deleted successfully");
-            } else {
-                println!("failed to delete post {id}");
+                if *dry_run && migrations.iter().all(|migration| migration.applied) {
+                    println!("database is up to date");
+                }
+
+                for migration in migrations {
+                    if *status {
+                        let state = if migration.applied {
+                            "applied"
+                        } else {
+                            "pending"
+                        };
+                        println!(
+                            "{}\t{}\t{}",
+                            migration.version, state, migration.description
+                        );
+                    } else if !migration.applied {
+                        println!(
+                            "would apply migration {} {}",
+                            migration.version, migration.description
+                        );
+                    }
+                }
+                return;
+            }
+
+            match migrate::run(&pool).await {
+                Ok(applied) if applied.is_empty() => println!("database is up to date"),
+                Ok(applied) => {
+                    for migration in applied {
+                        println!(
+                            "applied migration {} {}",
+                            migration.version, migration.description
+                        );
+                    }
+                }
+                Err(error) => eprintln!("failed to migrate database: {}", error),
+            }
+        }
+        Commands::Lockout { command } => {
+            let Some(pool) = connect_database().await else {
+                return;
+            };
+
+            let lockout_store = SqliteLockoutStore::new(pool);
+
+            match command {
+                LockoutCommands::List => {
+                    let lockouts = match lockout_store.list().await {
+                        Ok(lockouts) => lockouts,
+                        Err(error) => {
+                            eprintln!("failed to list lockouts: {}", error);
+                            return;
+                        }
+                    };
+
+                    let now = unix_now();
+                    for lockout in lockouts {
+                        let state = match lockout.locked_until {
+                            Some(locked_until) if locked_until > now => {
+                                format!("locked until {}", format_unix_time(locked_until))
+                            }
+                            _ => "not locked".to_string(),
+                        };
+                        println!(
+                            "{}\t{}\t{} failure(s), last at {}",
+                            lockout.post_id,
+                            state,
+                            lockout.failures,
+                            format_unix_time(lockout.last_failure_at)
+                        );
+                    }
+                }
+                LockoutCommands::Clear { id, all } => {
+                    let post_id = if *all { None } else { id.clone() };
+                    match lockout_store.clear(post_id).await {
+                        Ok(count) => println!("cleared {count} lockout(s)"),
+                        Err(error) => eprintln!("failed to clear lockouts: {}", error),
+                    }
+                }
+            }
+        }
+        Commands::Token { command } => {
+            let Some(pool) = connect_database().await else {
+                return;
+            };
+
+            let token_store = SqliteTokenStore::new(pool);
+
+            match command {
+                TokenCommands::Create { name, scopes } => {
+                    match token_store.create(name.clone(), scopes.clone()).await {
+                        Ok((token, secret)) => {
+                            println!("id of created token: {}", token.id);
+                            println!("secret (shown only once): {}", secret);
+                        }
+                        Err(error) => eprintln!("failed to create token: {}", error),
+                    }
+                }
+                TokenCommands::List => {
+                    let tokens = match token_store.list().await {
+                        Ok(tokens) => tokens,
+                        Err(error) => {
+                            eprintln!("failed to list tokens: {}", error);
+                            return;
+                        }
+                    };
+
+                    for token in tokens {
+                        let scopes = token
+                            .scopes
+                            .iter()
+                            .map(Scope::as_str)
+                            .collect::<Vec<_>>()
+                            .join(",");
+                        let last_used = token
+                            .last_used_at
+                            .map(format_unix_time)
+                            .unwrap_or_else(|| "never".to_string());
+                        let state = match token.revoked_at {
+                            Some(revoked_at) => format!("revoked {}", format_unix_time(revoked_at)),
+                            None => "active".to_string(),
+                        };
+                        println!(
+                            "{}\t{}\t{}\t{}\tlast used {}",
+                            token.id, token.name, scopes, state, last_used
+                        );
+                    }
+                }
+                TokenCommands::Revoke { id } => {
+                    match token_store.revoke(TokenId::from(id.clone())).await {
+                        Ok(true) => println!("token {id} revoked"),
+                        Ok(false) => println!("token {id} does not exist or is already revoked"),
+                        Err(error) => eprintln!("failed to revoke token: {}", error),
+                    }
+                }
             }
         }
     }
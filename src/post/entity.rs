@@ -1,13 +1,39 @@
+use serde::Serialize;
 use sqlx::{FromRow, Sqlite, Type};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, sqlx::Encode, sqlx::Decode)]
 pub struct PostId(String);
 
+/// First path segments of routes other than `/{id}`; a post with one of these
+/// ids could never be reached.
+const RESERVED_IDS: &[&str] = &[
+    "api", "assets", "feed", "page", "search", "sitemap", "static", "tags",
+];
+
 impl PostId {
     pub fn new(value: &str) -> Self {
         Self(value.to_string())
     }
+
+    /// Whether a new post may take this id.
+    pub fn is_valid(&self) -> bool {
+        self.is_well_formed() && !self.is_reserved()
+    }
+
+    /// Ids end up in URLs and file names, so only a conservative charset is allowed.
+    pub fn is_well_formed(&self) -> bool {
+        !self.0.is_empty()
+            && self.0.len() <= 128
+            && self
+                .0
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }
+
+    pub fn is_reserved(&self) -> bool {
+        RESERVED_IDS.contains(&self.0.as_str())
+    }
 }
 
 impl Default for PostId {
@@ -34,9 +60,9 @@ impl<'a> From<&'a str> for PostId {
     }
 }
 
-impl Into<String> for PostId {
-    fn into(self) -> String {
-        self.0
+impl From<PostId> for String {
+    fn from(value: PostId) -> Self {
+        value.0
     }
 }
 
@@ -50,8 +76,245 @@ impl Type<Sqlite> for PostId {
     }
 }
 
+/// A lowercase label grouping posts, used as a URL path segment.
+#[derive(
+    Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, sqlx::Encode, sqlx::Decode,
+)]
+#[serde(transparent)]
+pub struct Tag(String);
+
+impl Tag {
+    /// Lowercases `value`, drops a leading `#` and joins words with `-`.
+    /// Returns `None` unless what is left is letters, digits, `-` and `_`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let value = value.strip_prefix('#').unwrap_or(value);
+        let tag = value
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join("-")
+            .to_lowercase();
+
+        let valid = !tag.is_empty()
+            && tag.chars().count() <= 64
+            && tag
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+        valid.then_some(Self(tag))
+    }
+}
+
+impl std::fmt::Display for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::str::FromStr for Tag {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::parse(value)
+            .ok_or_else(|| format!("invalid tag `{value}`, expected letters, digits, '-' and '_'"))
+    }
+}
+
+impl From<Tag> for String {
+    fn from(value: Tag) -> Self {
+        value.0
+    }
+}
+
+impl Type<Sqlite> for Tag {
+    fn type_info() -> <Sqlite as sqlx::Database>::TypeInfo {
+        <String as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &<Sqlite as sqlx::Database>::TypeInfo) -> bool {
+        <String as Type<Sqlite>>::compatible(ty)
+    }
+}
+
+/// A tag with the number of listed posts carrying it.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct TagCount {
+    pub name: Tag,
+    pub posts: i64,
+}
+
 #[derive(Debug, Clone, FromRow)]
 pub struct Post {
     pub id: PostId,
     pub password: Option<String>,
+    /// Unix time after which the post is no longer served
+    pub expires_at: Option<i64>,
+    /// Number of reads after which the post is deleted
+    pub max_views: Option<i64>,
+    pub views: i64,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Byline shown on the post page
+    pub author: Option<String>,
+    pub created_at: i64,
+    /// Unix time of the last content or metadata change
+    pub updated_at: i64,
+    /// Unix time the post is listed under: its front matter date or `created_at`
+    pub published_at: i64,
+    /// Left out of listings; still served to anyone with the link
+    pub unlisted: bool,
+}
+
+impl Post {
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+            || self
+                .max_views
+                .is_some_and(|max_views| self.views >= max_views)
+    }
+}
+
+/// Everything needed to create a post.
+#[derive(Debug, Clone, Default)]
+pub struct NewPost {
+    pub id: Option<PostId>,
+    pub password: Option<String>,
+    pub content: String,
+    pub expires_at: Option<i64>,
+    pub max_views: Option<i64>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub unlisted: bool,
+    /// Added to the tags in the content's front matter
+    pub tags: Vec<Tag>,
+}
+
+/// Changes to a post applied together; `None` leaves a field as it is.
+#[derive(Debug, Clone, Default)]
+pub struct PostUpdate {
+    /// Recorded as a new revision
+    pub content: Option<String>,
+    /// `Some(None)` removes the password
+    pub password: Option<Option<String>>,
+    /// `Some(None)` clears the field
+    pub title: Option<Option<String>>,
+    pub description: Option<Option<String>>,
+    pub author: Option<Option<String>>,
+    pub unlisted: Option<bool>,
+}
+
+/// Position in the list of published posts, newest first. Written as
+/// `<published_at>.<id>` in URLs.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PublishedCursor {
+    pub published_at: i64,
+    pub id: PostId,
+}
+
+impl PublishedCursor {
+    pub fn new(post: &Post) -> Self {
+        Self {
+            published_at: post.published_at,
+            id: post.id.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for PublishedCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.published_at, self.id)
+    }
+}
+
+impl std::str::FromStr for PublishedCursor {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (published_at, id) = value.split_once('.').ok_or(())?;
+        let id = PostId::from(id);
+        if !id.is_well_formed() {
+            return Err(());
+        }
+
+        Ok(Self {
+            published_at: published_at.parse().map_err(|_| ())?,
+            id,
+        })
+    }
+}
+
+/// A post matching a search, best match first.
+#[derive(Debug, Clone, FromRow)]
+pub struct SearchHit {
+    #[sqlx(flatten)]
+    pub post: Post,
+    /// Title from the front matter or the stored one
+    pub indexed_title: Option<String>,
+    /// Text around the matches, each wrapped in [`MATCH_START`] and [`MATCH_END`]
+    ///
+    /// [`MATCH_START`]: crate::post::search::MATCH_START
+    /// [`MATCH_END`]: crate::post::search::MATCH_END
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct Revision {
+    pub number: i64,
+    pub content: String,
+    pub created_at: i64,
+    /// Token name or CLI command that made the change
+    pub author: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_cursor() {
+        let cursor = PublishedCursor {
+            published_at: 1704153600,
+            id: PostId::new("my-post_1"),
+        };
+
+        let parsed: PublishedCursor = cursor.to_string().parse().unwrap();
+
+        assert_eq!(cursor.to_string(), "1704153600.my-post_1");
+        assert_eq!(parsed.published_at, cursor.published_at);
+        assert_eq!(parsed.id, cursor.id);
+    }
+
+    #[test]
+    fn accepts_negative_times_and_reserved_ids() {
+        let parsed: PublishedCursor = "-5.page".parse().unwrap();
+
+        assert_eq!(parsed.published_at, -5);
+        assert_eq!(parsed.id, PostId::new("page"));
+    }
+
+    #[test]
+    fn rejects_bad_cursors() {
+        for cursor in [
+            "",
+            "1704153600",
+            ".post",
+            "1704153600.",
+            "soon.post",
+            "1.5.post",
+            "1704153600.a/b",
+            "1704153600.a b",
+        ] {
+            assert!(cursor.parse::<PublishedCursor>().is_err(), "{cursor:?}");
+        }
+    }
+
+    #[test]
+    fn reserves_route_prefixes() {
+        assert!(PostId::new("notes").is_valid());
+        assert!(PostId::new("page").is_well_formed());
+        assert!(PostId::new("page").is_reserved());
+        assert!(!PostId::new("page").is_valid());
+        assert!(!PostId::new("a.b").is_valid());
+        assert!(!PostId::new("").is_valid());
+    }
 }
@@ -0,0 +1,276 @@
+use std::time::UNIX_EPOCH;
+
+use serde::Deserialize;
+use thiserror::Error;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+use crate::post::entity::Tag;
+use crate::render::config::{MarkdownOverrides, MathSetting};
+
+#[derive(Debug, Error)]
+pub enum FrontMatterError {
+    #[error("front matter opened with `{0}` is never closed")]
+    Unterminated(&'static str),
+
+    #[error("invalid YAML front matter: {0}")]
+    Yaml(String),
+
+    #[error("invalid TOML front matter: {0}")]
+    Toml(String),
+
+    #[error("invalid front matter date `{0}`, expected YYYY-MM-DD or RFC 3339")]
+    InvalidDate(String),
+
+    #[error("invalid tag `{0}`, expected letters, digits, `-` and `_`")]
+    InvalidTag(String),
+}
+
+/// Per-post options set in a `---` YAML or `+++` TOML block at the top of the
+/// content. Unknown keys are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct FrontMatter {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Unix time shown as the publication date instead of `created_at`
+    pub date: Option<i64>,
+    pub tags: Vec<Tag>,
+    /// Renders the post with `<template>.html` instead of `post.html`
+    pub template: Option<String>,
+    /// Whether and where math is typeset; the server default unless set
+    pub math: Option<MathSetting>,
+    /// Whether the post gets a table of contents; a `[[toc]]` line decides if unset
+    pub toc: Option<bool>,
+    /// Drafts are not served
+    pub draft: bool,
+    /// Asks search engines not to index the post
+    pub noindex: bool,
+    pub markdown: MarkdownOverrides,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RawFrontMatter {
+    title: Option<String>,
+    description: Option<String>,
+    date: Option<RawDate>,
+    tags: Vec<String>,
+    template: Option<String>,
+    math: Option<MathSetting>,
+    toc: Option<bool>,
+    draft: bool,
+    noindex: bool,
+    markdown: MarkdownOverrides,
+}
+
+/// YAML dates are plain strings, TOML has a native datetime type.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawDate {
+    Text(String),
+    Toml(toml::value::Datetime),
+}
+
+/// Splits leading front matter off `content`, returning it with the remaining
+/// markdown. Content without front matter yields the defaults and is returned
+/// unchanged.
+pub fn parse(content: &str) -> Result<(FrontMatter, &str), FrontMatterError> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+
+    let Some((delimiter, block, body)) = ["---", "+++"]
+        .into_iter()
+        .find_map(|delimiter| split_block(content, delimiter).map(|(b, r)| (delimiter, b, r)))
+    else {
+        return Ok((FrontMatter::default(), content));
+    };
+    let block = match block {
+        Some(block) => block,
+        // An unclosed `---` is a thematic break, not front matter.
+        None if delimiter == "---" => return Ok((FrontMatter::default(), content)),
+        None => return Err(FrontMatterError::Unterminated(delimiter)),
+    };
+
+    let raw: RawFrontMatter = if delimiter == "---" {
+        if block.trim().is_empty() {
+            RawFrontMatter::default()
+        } else {
+            serde_yaml_ng::from_str(block)
+                .map_err(|error| FrontMatterError::Yaml(error.to_string()))?
+        }
+    } else {
+        toml::from_str(block).map_err(|error| FrontMatterError::Toml(error.to_string()))?
+    };
+
+    let date = match raw.date {
+        Some(RawDate::Text(text)) => Some(parse_date(&text)?),
+        Some(RawDate::Toml(datetime)) => Some(parse_date(&datetime.to_string())?),
+        None => None,
+    };
+
+    let tags = raw
+        .tags
+        .iter()
+        .map(|tag| Tag::parse(tag).ok_or_else(|| FrontMatterError::InvalidTag(tag.clone())))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let front_matter = FrontMatter {
+        title: raw.title,
+        description: raw.description,
+        date,
+        tags,
+        template: raw.template,
+        math: raw.math,
+        toc: raw.toc,
+        draft: raw.draft,
+        noindex: raw.noindex,
+        markdown: raw.markdown,
+    };
+
+    Ok((front_matter, body))
+}
+
+/// Returns `None` if `content` does not open with `delimiter` on a line of its
+/// own, otherwise the block between the delimiter lines (`None` when it is
+/// never closed) and the rest of the content.
+fn split_block<'a>(content: &'a str, delimiter: &str) -> Option<(Option<&'a str>, &'a str)> {
+    let first_line_end = content.find('\n')?;
+    if content[..first_line_end].trim_end() != delimiter {
+        return None;
+    }
+
+    let rest = &content[first_line_end + 1..];
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == delimiter {
+            return Some((Some(&rest[..offset]), &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+
+    Some((None, rest))
+}
+
+/// Accepts RFC 3339 timestamps and bare dates; times without an offset are
+/// taken as UTC.
+fn parse_date(text: &str) -> Result<i64, FrontMatterError> {
+    let text = text.trim();
+    let invalid = || FrontMatterError::InvalidDate(text.to_string());
+
+    if let Ok(datetime) = OffsetDateTime::parse(text, &Rfc3339) {
+        return Ok(datetime.unix_timestamp());
+    }
+
+    let time = if text.len() == 10 {
+        humantime::parse_rfc3339(&format!("{text}T00:00:00Z"))
+    } else {
+        humantime::parse_rfc3339_weak(text)
+    }
+    .map_err(|_| invalid())?;
+
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(front_matter: &FrontMatter) -> Vec<String> {
+        front_matter.tags.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn returns_content_without_front_matter_unchanged() {
+        let content = "# Title\n\n---\n\nbody\n";
+        let (front_matter, body) = parse(content).unwrap();
+
+        assert_eq!(body, content);
+        assert!(front_matter.title.is_none());
+        assert!(front_matter.tags.is_empty());
+        assert!(!front_matter.draft);
+    }
+
+    #[test]
+    fn parses_yaml() {
+        let content = "---\ntitle: Hello\ntags: [Rust, \"#Web Dev\"]\ndate: 2024-01-02\ndraft: true\nunknown: 1\n---\nbody\n";
+        let (front_matter, body) = parse(content).unwrap();
+
+        assert_eq!(body, "body\n");
+        assert_eq!(front_matter.title.as_deref(), Some("Hello"));
+        assert_eq!(tags(&front_matter), ["rust", "web-dev"]);
+        assert_eq!(front_matter.date, Some(1704153600));
+        assert!(front_matter.draft);
+    }
+
+    #[test]
+    fn parses_toml() {
+        let content = "+++\ntitle = \"Hello\"\ntags = [\"rust\"]\ndate = 2024-01-02T03:04:05Z\nnoindex = true\n+++\nbody\n";
+        let (front_matter, body) = parse(content).unwrap();
+
+        assert_eq!(body, "body\n");
+        assert_eq!(front_matter.title.as_deref(), Some("Hello"));
+        assert_eq!(tags(&front_matter), ["rust"]);
+        assert_eq!(front_matter.date, Some(1704164645));
+        assert!(front_matter.noindex);
+    }
+
+    #[test]
+    fn accepts_empty_yaml_block() {
+        let (front_matter, body) = parse("---\n---\nbody").unwrap();
+
+        assert_eq!(body, "body");
+        assert!(front_matter.title.is_none());
+    }
+
+    #[test]
+    fn rejects_malformed_yaml() {
+        let result = parse("---\ntitle: [unclosed\n---\nbody");
+
+        assert!(matches!(result, Err(FrontMatterError::Yaml(_))));
+    }
+
+    #[test]
+    fn rejects_malformed_toml() {
+        let result = parse("+++\ntitle = \n+++\nbody");
+
+        assert!(matches!(result, Err(FrontMatterError::Toml(_))));
+    }
+
+    #[test]
+    fn rejects_mistyped_keys() {
+        let result = parse("---\ndraft: sometimes\n---\nbody");
+
+        assert!(matches!(result, Err(FrontMatterError::Yaml(_))));
+    }
+
+    #[test]
+    fn treats_unclosed_dashes_as_markdown() {
+        let content = "---\n\n# Title\n\nbody\n";
+        let (front_matter, body) = parse(content).unwrap();
+
+        assert_eq!(body, content);
+        assert!(front_matter.title.is_none());
+    }
+
+    #[test]
+    fn rejects_unterminated_block() {
+        let result = parse("+++\ntitle = \"Hello\"\nbody");
+
+        assert!(matches!(result, Err(FrontMatterError::Unterminated("+++"))));
+    }
+
+    #[test]
+    fn rejects_invalid_tag() {
+        let result = parse("---\ntags: [ok, \"a/b\"]\n---\nbody");
+
+        assert!(matches!(result, Err(FrontMatterError::InvalidTag(tag)) if tag == "a/b"));
+    }
+
+    #[test]
+    fn rejects_invalid_date() {
+        let result = parse("---\ndate: yesterday\n---\nbody");
+
+        assert!(matches!(result, Err(FrontMatterError::InvalidDate(_))));
+    }
+}
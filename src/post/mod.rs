@@ -1,2 +1,6 @@
 pub mod entity;
+pub mod front_matter;
+pub mod password;
+pub mod search;
 pub mod store;
+pub mod sweeper;
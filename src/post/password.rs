@@ -0,0 +1,88 @@
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
+};
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PasswordError {
+    #[error("failed to hash password: {0}")]
+    FailedToHash(String),
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Verification {
+    Valid,
+    /// The password matched a plaintext value stored before hashing was introduced.
+    /// Callers should rehash it.
+    ValidLegacy,
+    Invalid,
+}
+
+pub fn hash(password: &str) -> Result<String, PasswordError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|error| PasswordError::FailedToHash(error.to_string()))
+}
+
+pub fn is_hashed(stored: &str) -> bool {
+    stored.starts_with("$argon2") && PasswordHash::new(stored).is_ok()
+}
+
+pub fn verify(stored: &str, candidate: &str) -> Verification {
+    if is_hashed(stored) {
+        let Ok(hash) = PasswordHash::new(stored) else {
+            return Verification::Invalid;
+        };
+
+        return match Argon2::default().verify_password(candidate.as_bytes(), &hash) {
+            Ok(()) => Verification::Valid,
+            Err(_) => Verification::Invalid,
+        };
+    }
+
+    if bool::from(stored.as_bytes().ct_eq(candidate.as_bytes())) {
+        Verification::ValidLegacy
+    } else {
+        Verification::Invalid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_a_hashed_password() {
+        let stored = hash("hunter2").unwrap();
+
+        assert!(is_hashed(&stored));
+        assert_eq!(verify(&stored, "hunter2"), Verification::Valid);
+        assert_eq!(verify(&stored, "hunter3"), Verification::Invalid);
+    }
+
+    #[test]
+    fn salts_every_hash() {
+        assert_ne!(hash("hunter2").unwrap(), hash("hunter2").unwrap());
+    }
+
+    #[test]
+    fn asks_for_a_rehash_of_a_matching_plaintext_password() {
+        assert!(!is_hashed("hunter2"));
+        assert_eq!(verify("hunter2", "hunter2"), Verification::ValidLegacy);
+        assert_eq!(verify("hunter2", "hunter"), Verification::Invalid);
+        assert_eq!(verify("hunter2", ""), Verification::Invalid);
+    }
+
+    #[test]
+    fn treats_a_malformed_hash_as_plaintext() {
+        let stored = "$argon2id$v=19$m=19456,t=2,p=1$c2FsdHNhbHQ$";
+
+        assert!(!is_hashed(stored));
+        assert_eq!(verify(stored, stored), Verification::ValidLegacy);
+        assert_eq!(verify(stored, "hunter2"), Verification::Invalid);
+    }
+}
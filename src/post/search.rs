@@ -0,0 +1,108 @@
+/// Written by FTS5 around each matched term of a snippet; neither can appear
+/// in text typed into a search box.
+pub const MATCH_START: char = '\u{2}';
+pub const MATCH_END: char = '\u{3}';
+
+/// Turns what a reader typed into an FTS5 query matching posts with every
+/// word, the last one also as a prefix. Quoting each word keeps FTS5 operators
+/// and punctuation from being interpreted. Returns `None` for a blank query.
+pub fn match_query(query: &str) -> Option<String> {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(|word| word.replace(['"', MATCH_START, MATCH_END], ""))
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"", word))
+        .collect();
+
+    if words.is_empty() {
+        return None;
+    }
+
+    Some(format!("{}*", words.join(" ")))
+}
+
+/// Escapes a snippet for HTML and wraps matched terms in `<mark>`.
+pub fn highlight_html(snippet: &str) -> String {
+    let mut html = String::with_capacity(snippet.len());
+    for c in snippet.chars() {
+        match c {
+            MATCH_START => html.push_str("<mark>"),
+            MATCH_END => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+
+    html
+}
+
+/// Marks matched terms of a snippet with `[` and `]` for a terminal.
+pub fn highlight_plain(snippet: &str) -> String {
+    snippet.replace(MATCH_START, "[").replace(MATCH_END, "]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_each_word_and_prefixes_the_last() {
+        assert_eq!(
+            match_query("  rust   web ").as_deref(),
+            Some("\"rust\" \"web\"*")
+        );
+    }
+
+    #[test]
+    fn strips_double_quotes() {
+        assert_eq!(
+            match_query("say \"hello\" \"").as_deref(),
+            Some("\"say\" \"hello\"*")
+        );
+        assert_eq!(match_query("a\"b").as_deref(), Some("\"ab\"*"));
+    }
+
+    #[test]
+    fn keeps_operators_and_punctuation_inside_quotes() {
+        assert_eq!(
+            match_query("NOT c++ OR title:x (a*) -b ^c").as_deref(),
+            Some("\"NOT\" \"c++\" \"OR\" \"title:x\" \"(a*)\" \"-b\" \"^c\"*")
+        );
+        assert_eq!(match_query("'it's'").as_deref(), Some("\"'it's'\"*"));
+    }
+
+    #[test]
+    fn strips_match_markers() {
+        let query = format!("{MATCH_START}rust{MATCH_END}");
+
+        assert_eq!(match_query(&query).as_deref(), Some("\"rust\"*"));
+    }
+
+    #[test]
+    fn ignores_blank_queries() {
+        assert_eq!(match_query(""), None);
+        assert_eq!(match_query(" \t\n"), None);
+        assert_eq!(match_query("\" \"\""), None);
+    }
+
+    #[test]
+    fn escapes_html_and_marks_matches() {
+        let snippet = format!("a <b> & {MATCH_START}\"c\"{MATCH_END} 'd'");
+
+        assert_eq!(
+            highlight_html(&snippet),
+            "a &lt;b&gt; &amp; <mark>&quot;c&quot;</mark> &#39;d&#39;"
+        );
+    }
+
+    #[test]
+    fn marks_matches_with_brackets() {
+        let snippet = format!("a {MATCH_START}<b>{MATCH_END} c");
+
+        assert_eq!(highlight_plain(&snippet), "a [<b>] c");
+    }
+}
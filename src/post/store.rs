@@ -1,76 +1,384 @@
 use sqlx::SqlitePool;
 use thiserror::Error;
 
-use crate::post::entity::{Post, PostId};
+use crate::clock::unix_now;
+use crate::post::entity::{
+    NewPost, Post, PostId, PostUpdate, PublishedCursor, Revision, SearchHit, Tag, TagCount,
+};
+use crate::post::front_matter::{self, FrontMatter};
+use crate::post::password;
+use crate::post::search::{self, MATCH_END, MATCH_START};
 
 #[derive(Debug, Error)]
 pub enum PostStoreError {
     #[error("post not found")]
     PostNotFound,
 
+    #[error("revision not found")]
+    RevisionNotFound,
+
     #[error("failed to create post: {0}")]
     FailedToCreatePost(String),
 
+    #[error("failed to update post: {0}")]
+    FailedToUpdatePost(String),
+
+    #[error("failed to delete post: {0}")]
+    FailedToDeletePost(String),
+
+    #[error("failed to hash password: {0}")]
+    FailedToHashPassword(String),
+
     #[error("unexpected error")]
     Unexpected,
 }
 
 pub trait PostStore {
-    async fn create(
+    async fn create(&self, post: NewPost, author: Option<String>)
+    -> Result<PostId, PostStoreError>;
+    async fn get_by_id(&self, id: PostId) -> Result<Post, PostStoreError>;
+    async fn get_content(&self, id: PostId) -> Result<String, PostStoreError>;
+    /// Replaces the content and records the new version as a revision.
+    async fn update_content(
+        &self,
+        id: PostId,
+        content: String,
+        author: Option<String>,
+    ) -> Result<i64, PostStoreError>;
+    /// Lists revisions oldest first; `content` is left empty to keep the listing small.
+    async fn list_revisions(&self, id: PostId) -> Result<Vec<Revision>, PostStoreError>;
+    async fn get_revision(&self, id: PostId, number: i64) -> Result<Revision, PostStoreError>;
+    /// Makes the content of revision `number` current again, recorded as a new revision.
+    async fn restore_revision(
         &self,
-        id: Option<PostId>,
+        id: PostId,
+        number: i64,
+        author: Option<String>,
+    ) -> Result<i64, PostStoreError>;
+    async fn update_password(
+        &self,
+        id: PostId,
         password: Option<String>,
-    ) -> Result<PostId, PostStoreError>;
-    async fn get_by_id(&self, id: PostId) -> Result<Post, PostStoreError>;
+    ) -> Result<(), PostStoreError>;
+    /// Applies every change in `update` or none of them; new content is
+    /// recorded as a revision by `author`.
+    async fn update(
+        &self,
+        id: PostId,
+        update: PostUpdate,
+        author: Option<String>,
+    ) -> Result<(), PostStoreError>;
+    async fn rehash_passwords(&self) -> Result<u64, PostStoreError>;
+    /// Returns up to `limit` posts ordered by id, starting after `after`.
+    async fn list(&self, after: Option<PostId>, limit: u32) -> Result<Vec<Post>, PostStoreError>;
+    /// Returns up to `limit` posts anyone may discover, newest first, starting
+    /// after `after` and skipping `offset` more: not protected, draft,
+    /// unlisted, expired or view-limited.
+    async fn list_published(
+        &self,
+        after: Option<PublishedCursor>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<Post>, PostStoreError>;
+    /// Returns up to `limit` published posts tagged `tag`, newest first,
+    /// starting after `after` and skipping `offset` more.
+    async fn list_tagged(
+        &self,
+        tag: Tag,
+        after: Option<PublishedCursor>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<Post>, PostStoreError>;
+    async fn get_tags(&self, id: PostId) -> Result<Vec<Tag>, PostStoreError>;
+    /// Adds tags that stay when the front matter changes.
+    async fn tag(&self, id: PostId, tags: Vec<Tag>) -> Result<(), PostStoreError>;
+    /// Removes tags; ones listed in the front matter return when the content is next saved.
+    async fn untag(&self, id: PostId, tags: Vec<Tag>) -> Result<(), PostStoreError>;
+    /// Returns up to `limit` published posts search engines may index, most
+    /// recently updated first.
+    async fn list_indexable(&self, limit: u32) -> Result<Vec<Post>, PostStoreError>;
+    /// Lists tags of published posts with how many carry each, most used first.
+    async fn list_tags(&self) -> Result<Vec<TagCount>, PostStoreError>;
+    /// Returns up to `limit` posts matching `query`, best match first. Only
+    /// posts that could be listed are searched, plus the protected ones in
+    /// `unlocked`.
+    async fn search(
+        &self,
+        query: &str,
+        unlocked: Vec<PostId>,
+        limit: u32,
+    ) -> Result<Vec<SearchHit>, PostStoreError>;
+    /// Counts a read of the post. Returns `false` if the post has expired in
+    /// the meantime; a post that reaches its view limit is deleted right away.
+    async fn record_view(&self, id: PostId) -> Result<bool, PostStoreError>;
+    /// Deletes posts past their expiry or view limit and returns how many were removed.
+    async fn delete_expired(&self) -> Result<u64, PostStoreError>;
     async fn delete_by_id(&self, id: PostId) -> Result<bool, PostStoreError>;
 }
 
+const POST_COLUMNS: &str = "id, password, expires_at, max_views, views, \
+    title, description, author, created_at, updated_at, published_at, unlisted";
+
+/// Posts anyone may discover; `$1` is the current time.
+const LISTED: &str = "password IS NULL AND NOT draft AND NOT unlisted
+    AND (expires_at IS NULL OR expires_at > $1) AND max_views IS NULL";
+
 pub struct SqlitePostStore {
     pool: SqlitePool,
 }
 
 impl SqlitePostStore {
-    pub async fn new(pool: SqlitePool) -> Result<Self, sqlx::Error> {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+fn unexpected(error: sqlx::Error) -> PostStoreError {
+    eprintln!("{}", error);
+    PostStoreError::Unexpected
+}
+
+/// Front matter of content about to be stored. Content is checked before it
+/// gets here, so anything unparsable is stored as if it had none.
+fn stored_front_matter(content: &str) -> FrontMatter {
+    front_matter::parse(content)
+        .map(|(front_matter, _)| front_matter)
+        .unwrap_or_default()
+}
+
+/// Appends the next revision of a post inside the caller's transaction.
+async fn record_revision(
+    connection: &mut sqlx::SqliteConnection,
+    id: &PostId,
+    content: &str,
+    author: Option<String>,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        "INSERT INTO post_revisions (post_id, number, content, created_at, author)
+        SELECT $1, COALESCE(MAX(number), 0) + 1, $2, $3, $4
+        FROM post_revisions WHERE post_id = $1
+        RETURNING number",
+    )
+    .bind(id.clone())
+    .bind(content)
+    .bind(unix_now())
+    .bind(author)
+    .fetch_one(connection)
+    .await
+}
+
+/// Replaces the content of a post inside the caller's transaction, syncing
+/// what comes from its front matter and recording a revision. Returns `None`
+/// if there is no such post.
+async fn write_content(
+    connection: &mut sqlx::SqliteConnection,
+    id: &PostId,
+    content: &str,
+    author: Option<String>,
+) -> Result<Option<i64>, sqlx::Error> {
+    let front_matter = stored_front_matter(content);
+    let result = sqlx::query(
+        "UPDATE posts SET content = $1, updated_at = $2,
+            published_at = COALESCE($3, created_at), draft = $4, noindex = $5
+        WHERE id = $6",
+    )
+    .bind(content)
+    .bind(unix_now())
+    .bind(front_matter.date)
+    .bind(front_matter.draft)
+    .bind(front_matter.noindex)
+    .bind(id.clone())
+    .execute(&mut *connection)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    sync_front_matter_tags(connection, id, &front_matter.tags).await?;
+    record_revision(connection, id, content, author)
+        .await
+        .map(Some)
+}
+
+/// Links `tags` to a post inside the caller's transaction, creating the ones
+/// that do not exist yet. A tag added by hand stays so even if the front
+/// matter lists it too.
+async fn link_tags(
+    connection: &mut sqlx::SqliteConnection,
+    id: &PostId,
+    tags: &[Tag],
+    front_matter: bool,
+) -> Result<(), sqlx::Error> {
+    for tag in tags {
+        sqlx::query("INSERT INTO tags (name) VALUES ($1) ON CONFLICT (name) DO NOTHING")
+            .bind(tag)
+            .execute(&mut *connection)
+            .await?;
         sqlx::query(
-            "CREATE TABLE IF NOT EXISTS posts (
-            id TEXT PRIMARY KEY,
-            password TEXT NOT NULL
-        )",
+            "INSERT INTO post_tags (post_id, tag_id, front_matter)
+            SELECT $1, id, $3 FROM tags WHERE name = $2
+            ON CONFLICT (post_id, tag_id)
+                DO UPDATE SET front_matter = front_matter AND excluded.front_matter",
         )
-        .execute(&pool)
+        .bind(id.clone())
+        .bind(tag)
+        .bind(front_matter)
+        .execute(&mut *connection)
         .await?;
-
-        Ok(Self { pool })
     }
+
+    Ok(())
+}
+
+/// Replaces the tags a post got from its front matter with `tags`.
+pub async fn sync_front_matter_tags(
+    connection: &mut sqlx::SqliteConnection,
+    id: &PostId,
+    tags: &[Tag],
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM post_tags WHERE post_id = $1 AND front_matter")
+        .bind(id.clone())
+        .execute(&mut *connection)
+        .await?;
+
+    link_tags(connection, id, tags, true).await
+}
+
+/// Rewrites the search index row of a post from its stored metadata, tags
+/// and content.
+pub async fn index_post(
+    connection: &mut sqlx::SqliteConnection,
+    id: &PostId,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM post_search WHERE post_id = $1")
+        .bind(id.clone())
+        .execute(&mut *connection)
+        .await?;
+
+    let row: Option<(Option<String>, Option<String>, String)> =
+        sqlx::query_as("SELECT title, description, content FROM posts WHERE id = $1")
+            .bind(id.clone())
+            .fetch_optional(&mut *connection)
+            .await?;
+    let Some((title, description, content)) = row else {
+        return Ok(());
+    };
+
+    let (front_matter, body) = front_matter::parse(&content)
+        .unwrap_or_else(|_| (FrontMatter::default(), content.as_str()));
+    let tags: Vec<String> = sqlx::query_scalar(
+        "SELECT tags.name FROM post_tags
+        JOIN tags ON tags.id = post_tags.tag_id
+        WHERE post_tags.post_id = $1",
+    )
+    .bind(id.clone())
+    .fetch_all(&mut *connection)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO post_search (post_id, title, description, tags, body)
+        VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(id.clone())
+    .bind(front_matter.title.or(title))
+    .bind(front_matter.description.or(description))
+    .bind(tags.join(" "))
+    .bind(body)
+    .execute(&mut *connection)
+    .await?;
+
+    Ok(())
+}
+
+/// Argon2 is deliberately slow, so hashing runs on the blocking thread pool.
+async fn hash_password(password: String) -> Result<String, PostStoreError> {
+    tokio::task::spawn_blocking(move || password::hash(&password))
+        .await
+        .map_err(|_| PostStoreError::Unexpected)?
+        .map_err(|error| PostStoreError::FailedToHashPassword(error.to_string()))
 }
 
 impl PostStore for SqlitePostStore {
     async fn create(
         &self,
-        id: Option<PostId>,
-        password: Option<String>,
+        post: NewPost,
+        author: Option<String>,
     ) -> Result<PostId, PostStoreError> {
+        let NewPost {
+            id,
+            password,
+            content,
+            expires_at,
+            max_views,
+            title,
+            description,
+            author: byline,
+            unlisted,
+            tags,
+        } = post;
         let id = id.unwrap_or_default();
-        if let Some(error) = sqlx::query("INSERT INTO posts (id, password) VALUES ($1, $2)")
-            .bind(id.clone())
-            .bind(password)
-            .execute(&self.pool)
-            .await
-            .err()
+        let password = match password {
+            Some(password) => Some(hash_password(password).await?),
+            None => None,
+        };
+
+        let mut transaction = self.pool.begin().await.map_err(unexpected)?;
+
+        let now = unix_now();
+        let front_matter = stored_front_matter(&content);
+        if let Some(error) = sqlx::query(
+            "INSERT INTO posts (
+                id, password, content, expires_at, max_views,
+                title, description, author, created_at, updated_at,
+                published_at, draft, noindex, unlisted
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $9, $10, $11, $12, $13)",
+        )
+        .bind(id.clone())
+        .bind(password)
+        .bind(&content)
+        .bind(expires_at)
+        .bind(max_views)
+        .bind(title)
+        .bind(description)
+        .bind(byline)
+        .bind(now)
+        .bind(front_matter.date.unwrap_or(now))
+        .bind(front_matter.draft)
+        .bind(front_matter.noindex)
+        .bind(unlisted)
+        .execute(&mut *transaction)
+        .await
+        .err()
         {
             eprintln!("{}", error);
-            Err(PostStoreError::FailedToCreatePost(error.to_string()))
-        } else {
-            Ok(id)
+            return Err(PostStoreError::FailedToCreatePost(error.to_string()));
         }
+
+        let failed = |error: sqlx::Error| PostStoreError::FailedToCreatePost(error.to_string());
+        link_tags(&mut transaction, &id, &tags, false)
+            .await
+            .map_err(failed)?;
+        sync_front_matter_tags(&mut transaction, &id, &front_matter.tags)
+            .await
+            .map_err(failed)?;
+        index_post(&mut transaction, &id).await.map_err(failed)?;
+        record_revision(&mut transaction, &id, &content, author)
+            .await
+            .map_err(failed)?;
+        transaction.commit().await.map_err(unexpected)?;
+
+        Ok(id)
     }
 
     async fn get_by_id(&self, id: PostId) -> Result<Post, PostStoreError> {
-        let post: Option<Post> = sqlx::query_as("SELECT id, password FROM posts WHERE id = ?")
-            .bind(id.to_string())
-            .fetch_optional(&self.pool)
-            .await
-            .unwrap();
+        let post: Option<Post> =
+            sqlx::query_as(&format!("SELECT {POST_COLUMNS} FROM posts WHERE id = ?"))
+                .bind(id.to_string())
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(unexpected)?;
 
         if let Some(post) = post {
             Ok(post)
@@ -79,17 +387,630 @@ impl PostStore for SqlitePostStore {
         }
     }
 
+    async fn get_content(&self, id: PostId) -> Result<String, PostStoreError> {
+        let content: Option<String> = sqlx::query_scalar("SELECT content FROM posts WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(unexpected)?;
+
+        content.ok_or(PostStoreError::PostNotFound)
+    }
+
+    async fn update_content(
+        &self,
+        id: PostId,
+        content: String,
+        author: Option<String>,
+    ) -> Result<i64, PostStoreError> {
+        let failed = |error: sqlx::Error| PostStoreError::FailedToUpdatePost(error.to_string());
+        let mut transaction = self.pool.begin().await.map_err(unexpected)?;
+
+        let number = write_content(&mut transaction, &id, &content, author)
+            .await
+            .map_err(failed)?
+            .ok_or(PostStoreError::PostNotFound)?;
+        index_post(&mut transaction, &id).await.map_err(failed)?;
+        transaction.commit().await.map_err(failed)?;
+
+        Ok(number)
+    }
+
+    async fn list_revisions(&self, id: PostId) -> Result<Vec<Revision>, PostStoreError> {
+        self.get_by_id(id.clone()).await?;
+
+        sqlx::query_as(
+            "SELECT number, '' AS content, created_at, author FROM post_revisions
+            WHERE post_id = $1 ORDER BY number",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(unexpected)
+    }
+
+    async fn get_revision(&self, id: PostId, number: i64) -> Result<Revision, PostStoreError> {
+        let revision: Option<Revision> = sqlx::query_as(
+            "SELECT number, content, created_at, author FROM post_revisions
+            WHERE post_id = $1 AND number = $2",
+        )
+        .bind(id)
+        .bind(number)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(unexpected)?;
+
+        revision.ok_or(PostStoreError::RevisionNotFound)
+    }
+
+    async fn restore_revision(
+        &self,
+        id: PostId,
+        number: i64,
+        author: Option<String>,
+    ) -> Result<i64, PostStoreError> {
+        let revision = self.get_revision(id.clone(), number).await?;
+        self.update_content(id, revision.content, author).await
+    }
+
+    async fn update_password(
+        &self,
+        id: PostId,
+        password: Option<String>,
+    ) -> Result<(), PostStoreError> {
+        let password = match password {
+            Some(password) => Some(hash_password(password).await?),
+            None => None,
+        };
+
+        let result = sqlx::query("UPDATE posts SET password = $1 WHERE id = $2")
+            .bind(password)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|error| PostStoreError::FailedToUpdatePost(error.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            Err(PostStoreError::PostNotFound)
+        } else {
+            Ok(())
+        }
+    }
+
+    async fn update(
+        &self,
+        id: PostId,
+        update: PostUpdate,
+        author: Option<String>,
+    ) -> Result<(), PostStoreError> {
+        let failed = |error: sqlx::Error| PostStoreError::FailedToUpdatePost(error.to_string());
+        let password = match update.password {
+            Some(Some(password)) => Some(Some(hash_password(password).await?)),
+            password => password,
+        };
+        let mut transaction = self.pool.begin().await.map_err(unexpected)?;
+
+        let exists: Option<i64> = sqlx::query_scalar("SELECT 1 FROM posts WHERE id = $1")
+            .bind(id.clone())
+            .fetch_optional(&mut *transaction)
+            .await
+            .map_err(unexpected)?;
+        if exists.is_none() {
+            return Err(PostStoreError::PostNotFound);
+        }
+
+        if let Some(password) = password {
+            sqlx::query("UPDATE posts SET password = $1 WHERE id = $2")
+                .bind(password)
+                .bind(id.clone())
+                .execute(&mut *transaction)
+                .await
+                .map_err(failed)?;
+        }
+
+        let metadata = [
+            ("title", update.title),
+            ("description", update.description),
+            ("author", update.author),
+        ];
+        let mut changed = update.content.is_some() || update.unlisted.is_some();
+        for (column, value) in metadata {
+            let Some(value) = value else {
+                continue;
+            };
+
+            sqlx::query(&format!("UPDATE posts SET {column} = $1 WHERE id = $2"))
+                .bind(value)
+                .bind(id.clone())
+                .execute(&mut *transaction)
+                .await
+                .map_err(failed)?;
+            changed = true;
+        }
+
+        if let Some(unlisted) = update.unlisted {
+            sqlx::query("UPDATE posts SET unlisted = $1 WHERE id = $2")
+                .bind(unlisted)
+                .bind(id.clone())
+                .execute(&mut *transaction)
+                .await
+                .map_err(failed)?;
+        }
+
+        if let Some(content) = &update.content {
+            write_content(&mut transaction, &id, content, author)
+                .await
+                .map_err(failed)?;
+        }
+
+        if changed {
+            sqlx::query("UPDATE posts SET updated_at = $1 WHERE id = $2")
+                .bind(unix_now())
+                .bind(id.clone())
+                .execute(&mut *transaction)
+                .await
+                .map_err(failed)?;
+            index_post(&mut transaction, &id).await.map_err(failed)?;
+        }
+
+        transaction.commit().await.map_err(failed)
+    }
+
+    async fn rehash_passwords(&self) -> Result<u64, PostStoreError> {
+        let rows: Vec<(PostId, String)> =
+            sqlx::query_as("SELECT id, password FROM posts WHERE password IS NOT NULL")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(unexpected)?;
+
+        let mut rehashed = 0;
+        for (id, stored) in rows {
+            if password::is_hashed(&stored) {
+                continue;
+            }
+
+            self.update_password(id, Some(stored)).await?;
+            rehashed += 1;
+        }
+
+        Ok(rehashed)
+    }
+
+    async fn list(&self, after: Option<PostId>, limit: u32) -> Result<Vec<Post>, PostStoreError> {
+        sqlx::query_as(&format!(
+            "SELECT {POST_COLUMNS} FROM posts WHERE $1 IS NULL OR id > $1 ORDER BY id LIMIT $2"
+        ))
+        .bind(after)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(unexpected)
+    }
+
+    async fn list_published(
+        &self,
+        after: Option<PublishedCursor>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<Post>, PostStoreError> {
+        let (published_at, id) = match after {
+            Some(cursor) => (Some(cursor.published_at), Some(cursor.id)),
+            None => (None, None),
+        };
+
+        sqlx::query_as(&format!(
+            "SELECT {POST_COLUMNS} FROM posts
+            WHERE {LISTED} AND ($2 IS NULL OR (published_at, id) < ($2, $3))
+            ORDER BY published_at DESC, id DESC
+            LIMIT $4 OFFSET $5"
+        ))
+        .bind(unix_now())
+        .bind(published_at)
+        .bind(id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(unexpected)
+    }
+
+    async fn list_tagged(
+        &self,
+        tag: Tag,
+        after: Option<PublishedCursor>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<Post>, PostStoreError> {
+        let (published_at, id) = match after {
+            Some(cursor) => (Some(cursor.published_at), Some(cursor.id)),
+            None => (None, None),
+        };
+
+        sqlx::query_as(&format!(
+            "SELECT {POST_COLUMNS} FROM posts
+            WHERE {LISTED} AND ($2 IS NULL OR (published_at, id) < ($2, $3))
+                AND id IN (
+                    SELECT post_id FROM post_tags
+                    JOIN tags ON tags.id = post_tags.tag_id
+                    WHERE tags.name = $6
+                )
+            ORDER BY published_at DESC, id DESC
+            LIMIT $4 OFFSET $5"
+        ))
+        .bind(unix_now())
+        .bind(published_at)
+        .bind(id)
+        .bind(limit)
+        .bind(offset)
+        .bind(tag)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(unexpected)
+    }
+
+    async fn get_tags(&self, id: PostId) -> Result<Vec<Tag>, PostStoreError> {
+        sqlx::query_scalar(
+            "SELECT tags.name FROM post_tags
+            JOIN tags ON tags.id = post_tags.tag_id
+            WHERE post_tags.post_id = $1
+            ORDER BY tags.name",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(unexpected)
+    }
+
+    async fn tag(&self, id: PostId, tags: Vec<Tag>) -> Result<(), PostStoreError> {
+        let failed = |error: sqlx::Error| PostStoreError::FailedToUpdatePost(error.to_string());
+        let mut transaction = self.pool.begin().await.map_err(unexpected)?;
+
+        let result = sqlx::query("UPDATE posts SET updated_at = $1 WHERE id = $2")
+            .bind(unix_now())
+            .bind(id.clone())
+            .execute(&mut *transaction)
+            .await
+            .map_err(failed)?;
+
+        if result.rows_affected() == 0 {
+            return Err(PostStoreError::PostNotFound);
+        }
+
+        link_tags(&mut transaction, &id, &tags, false)
+            .await
+            .map_err(failed)?;
+        index_post(&mut transaction, &id).await.map_err(failed)?;
+        transaction.commit().await.map_err(failed)
+    }
+
+    async fn untag(&self, id: PostId, tags: Vec<Tag>) -> Result<(), PostStoreError> {
+        let failed = |error: sqlx::Error| PostStoreError::FailedToUpdatePost(error.to_string());
+        let mut transaction = self.pool.begin().await.map_err(unexpected)?;
+
+        let result = sqlx::query("UPDATE posts SET updated_at = $1 WHERE id = $2")
+            .bind(unix_now())
+            .bind(id.clone())
+            .execute(&mut *transaction)
+            .await
+            .map_err(failed)?;
+
+        if result.rows_affected() == 0 {
+            return Err(PostStoreError::PostNotFound);
+        }
+
+        for tag in tags {
+            sqlx::query(
+                "DELETE FROM post_tags
+                WHERE post_id = $1 AND tag_id = (SELECT id FROM tags WHERE name = $2)",
+            )
+            .bind(id.clone())
+            .bind(tag)
+            .execute(&mut *transaction)
+            .await
+            .map_err(failed)?;
+        }
+
+        index_post(&mut transaction, &id).await.map_err(failed)?;
+        transaction.commit().await.map_err(failed)
+    }
+
+    async fn list_indexable(&self, limit: u32) -> Result<Vec<Post>, PostStoreError> {
+        sqlx::query_as(&format!(
+            "SELECT {POST_COLUMNS} FROM posts WHERE {LISTED} AND NOT noindex
+            ORDER BY updated_at DESC, id DESC
+            LIMIT $2"
+        ))
+        .bind(unix_now())
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(unexpected)
+    }
+
+    async fn list_tags(&self) -> Result<Vec<TagCount>, PostStoreError> {
+        sqlx::query_as(&format!(
+            "SELECT tags.name AS name, COUNT(*) AS posts FROM tags
+            JOIN post_tags ON post_tags.tag_id = tags.id
+            JOIN posts ON posts.id = post_tags.post_id
+            WHERE {LISTED}
+            GROUP BY tags.id
+            ORDER BY posts DESC, name"
+        ))
+        .bind(unix_now())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(unexpected)
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        unlocked: Vec<PostId>,
+        limit: u32,
+    ) -> Result<Vec<SearchHit>, PostStoreError> {
+        let Some(query) = search::match_query(query) else {
+            return Ok(Vec::new());
+        };
+        let unlocked_params = (0..unlocked.len())
+            .map(|index| format!("${}", index + 6))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        // Title, description and tags count for more than the body.
+        let sql = format!(
+            "SELECT {POST_COLUMNS}, hits.indexed_title, hits.snippet FROM (
+                SELECT post_id, NULLIF(title, '') AS indexed_title,
+                    snippet(post_search, -1, $2, $3, '…', 24) AS snippet,
+                    bm25(post_search, 0.0, 10.0, 5.0, 5.0, 1.0) AS rank
+                FROM post_search WHERE post_search MATCH $4
+            ) AS hits
+            JOIN posts ON posts.id = hits.post_id
+            WHERE NOT draft AND NOT unlisted
+                AND (expires_at IS NULL OR expires_at > $1) AND max_views IS NULL
+                AND (password IS NULL OR id IN ({unlocked_params}))
+            ORDER BY hits.rank
+            LIMIT $5"
+        );
+        let mut hits = sqlx::query_as(&sql)
+            .bind(unix_now())
+            .bind(MATCH_START.to_string())
+            .bind(MATCH_END.to_string())
+            .bind(query)
+            .bind(limit);
+        for id in unlocked {
+            hits = hits.bind(id);
+        }
+
+        hits.fetch_all(&self.pool).await.map_err(unexpected)
+    }
+
+    async fn record_view(&self, id: PostId) -> Result<bool, PostStoreError> {
+        let row: Option<(i64, Option<i64>)> = sqlx::query_as(
+            "UPDATE posts SET views = views + 1
+            WHERE id = $1
+                AND (expires_at IS NULL OR expires_at > $2)
+                AND (max_views IS NULL OR views < max_views)
+            RETURNING views, max_views",
+        )
+        .bind(id.clone())
+        .bind(unix_now())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(unexpected)?;
+
+        let Some((views, max_views)) = row else {
+            return Ok(false);
+        };
+
+        if max_views.is_some_and(|max_views| views >= max_views) {
+            self.delete_by_id(id).await?;
+        }
+
+        Ok(true)
+    }
+
+    async fn delete_expired(&self) -> Result<u64, PostStoreError> {
+        sqlx::query(
+            "DELETE FROM posts
+            WHERE expires_at <= $1 OR (max_views IS NOT NULL AND views >= max_views)",
+        )
+        .bind(unix_now())
+        .execute(&self.pool)
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(|error| PostStoreError::FailedToDeletePost(error.to_string()))
+    }
+
     async fn delete_by_id(&self, id: PostId) -> Result<bool, PostStoreError> {
-        if let Some(error) = sqlx::query("DELETE FROM posts WHERE id = ?")
+        match sqlx::query("DELETE FROM posts WHERE id = ?")
             .bind(id.clone())
             .execute(&self.pool)
             .await
-            .err()
         {
-            eprintln!("{}", error);
-            Err(PostStoreError::FailedToCreatePost(error.to_string()))
+            Ok(result) => Ok(result.rows_affected() > 0),
+            Err(error) => {
+                eprintln!("{}", error);
+                Err(PostStoreError::FailedToDeletePost(error.to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database;
+
+    const PER_PAGE: u32 = 3;
+
+    async fn store() -> SqlitePostStore {
+        SqlitePostStore::new(database::connect_migrated().await)
+    }
+
+    /// Creates a public post dated `day` of January 2024 with `front_matter`
+    /// added to its front matter.
+    async fn create(store: &SqlitePostStore, id: &str, day: u32, front_matter: &str) {
+        let post = NewPost {
+            id: Some(PostId::new(id)),
+            content: format!("---\ndate: 2024-01-{day:02}\n{front_matter}---\nbody\n"),
+            ..NewPost::default()
+        };
+
+        store.create(post, None).await.unwrap();
+    }
+
+    fn ids(posts: &[Post]) -> Vec<String> {
+        posts.iter().map(|post| post.id.to_string()).collect()
+    }
+
+    /// Fetches page `number` the way the index does without a cursor: the
+    /// last post of the previous page is looked up by offset, along with the
+    /// one after it to tell that the page is not empty.
+    async fn page(store: &SqlitePostStore, tag: Option<&Tag>, number: u32) -> Option<Vec<Post>> {
+        let list = |after, offset, limit| async move {
+            match tag {
+                Some(tag) => store.list_tagged(tag.clone(), after, offset, limit).await,
+                None => store.list_published(after, offset, limit).await,
+            }
+            .unwrap()
+        };
+
+        let after = if number == 1 {
+            None
         } else {
-            Ok(true)
+            let skipped = (number - 1) * PER_PAGE;
+            let previous = list(None, skipped - 1, 2).await;
+            let [last, _] = previous.as_slice() else {
+                return None;
+            };
+            Some(PublishedCursor::new(last))
+        };
+
+        Some(list(after, 0, PER_PAGE).await)
+    }
+
+    /// Seven listed posts, newest first `g` to `a`; `c` and `d` share a date.
+    async fn seed(store: &SqlitePostStore) {
+        for (id, day) in [("a", 1), ("b", 2), ("c", 3), ("d", 3), ("e", 5), ("f", 6)] {
+            create(store, id, day, "tags: [even]\n").await;
         }
+        create(store, "g", 7, "").await;
+    }
+
+    #[tokio::test]
+    async fn pages_by_offset() {
+        let store = store().await;
+        seed(&store).await;
+
+        assert_eq!(ids(&page(&store, None, 1).await.unwrap()), ["g", "f", "e"]);
+        assert_eq!(ids(&page(&store, None, 2).await.unwrap()), ["d", "c", "b"]);
+        assert_eq!(ids(&page(&store, None, 3).await.unwrap()), ["a"]);
+        assert!(page(&store, None, 4).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn offset_pages_match_cursor_pages() {
+        let store = store().await;
+        seed(&store).await;
+
+        let mut after = None;
+        for number in 1..=3 {
+            let by_cursor = store.list_published(after, 0, PER_PAGE).await.unwrap();
+            let by_offset = page(&store, None, number).await.unwrap();
+
+            assert_eq!(ids(&by_cursor), ids(&by_offset), "page {number}");
+            after = by_cursor.last().map(PublishedCursor::new);
+        }
+
+        let rest = store.list_published(after, 0, PER_PAGE).await.unwrap();
+        assert!(rest.is_empty());
+    }
+
+    #[tokio::test]
+    async fn pages_tagged_posts() {
+        let store = store().await;
+        seed(&store).await;
+        let tag = Tag::parse("even").unwrap();
+
+        assert_eq!(
+            ids(&page(&store, Some(&tag), 1).await.unwrap()),
+            ["f", "e", "d"]
+        );
+        assert_eq!(
+            ids(&page(&store, Some(&tag), 2).await.unwrap()),
+            ["c", "b", "a"]
+        );
+        assert!(page(&store, Some(&tag), 3).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn leaves_out_unlisted_posts() {
+        let store = store().await;
+        create(&store, "public", 1, "").await;
+        create(&store, "draft", 2, "draft: true\n").await;
+        let hidden = [
+            NewPost {
+                id: Some(PostId::new("protected")),
+                password: Some("secret".to_string()),
+                ..NewPost::default()
+            },
+            NewPost {
+                id: Some(PostId::new("unlisted")),
+                unlisted: true,
+                ..NewPost::default()
+            },
+            NewPost {
+                id: Some(PostId::new("once")),
+                max_views: Some(1),
+                ..NewPost::default()
+            },
+        ];
+        for post in hidden {
+            store.create(post, None).await.unwrap();
+        }
+
+        let posts = store.list_published(None, 0, 10).await.unwrap();
+
+        assert_eq!(ids(&posts), ["public"]);
+    }
+
+    #[tokio::test]
+    async fn continues_after_cursor_of_deleted_post() {
+        let store = store().await;
+        seed(&store).await;
+        let first = store.list_published(None, 0, PER_PAGE).await.unwrap();
+        let after = PublishedCursor::new(first.last().unwrap());
+
+        store.delete_by_id(PostId::new("e")).await.unwrap();
+        let next = store
+            .list_published(Some(after), 0, PER_PAGE)
+            .await
+            .unwrap();
+
+        assert_eq!(ids(&next), ["d", "c", "b"]);
+    }
+
+    #[tokio::test]
+    async fn ignores_cursor_past_the_end() {
+        let store = store().await;
+        seed(&store).await;
+
+        let after: PublishedCursor = "0.zzz".parse().unwrap();
+        assert!(
+            store
+                .list_published(Some(after), 0, PER_PAGE)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let after: PublishedCursor = format!("{}.zzz", i64::MAX).parse().unwrap();
+        assert_eq!(
+            ids(&store
+                .list_published(Some(after), 0, PER_PAGE)
+                .await
+                .unwrap()),
+            ["g", "f", "e"]
+        );
     }
 }
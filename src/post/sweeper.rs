@@ -0,0 +1,24 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+
+use crate::post::store::{PostStore, SqlitePostStore};
+
+/// Periodically deletes posts that expired or used up their views, so they
+/// do not linger in the database until someone requests them.
+pub fn spawn_sweeper(store: Arc<SqlitePostStore>, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            match store.delete_expired().await {
+                Ok(0) => {}
+                Ok(count) => println!("deleted {count} expired post(s)"),
+                Err(error) => eprintln!("failed to delete expired posts: {}", error),
+            }
+        }
+    })
+}
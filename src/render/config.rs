@@ -0,0 +1,160 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RenderConfigError {
+    #[error("failed to read render config: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("invalid render config: {0}")]
+    Toml(#[from] toml::de::Error),
+}
+
+/// Markdown extensions enabled for every post, read from a TOML file whose
+/// keys match the field names.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarkdownConfig {
+    pub table: bool,
+    pub strikethrough: bool,
+    pub autolink: bool,
+    pub tasklist: bool,
+    pub footnotes: bool,
+    pub superscript: bool,
+    pub description_lists: bool,
+    /// Adds an `id` to every heading so sections can be linked to
+    pub header_ids: bool,
+    /// `||hidden||` text
+    pub spoiler: bool,
+    /// `[[target|title]]` links
+    pub wikilinks: bool,
+    pub gfm_quirks: bool,
+    pub tasklist_classes: bool,
+    /// Curly quotes, dashes and ellipses
+    pub smart: bool,
+    /// Treats every newline in a paragraph as a line break
+    pub hardbreaks: bool,
+    /// Passes raw HTML through instead of escaping it
+    pub unsafe_html: bool,
+}
+
+impl Default for MarkdownConfig {
+    fn default() -> Self {
+        Self {
+            table: true,
+            strikethrough: false,
+            autolink: true,
+            tasklist: false,
+            footnotes: false,
+            superscript: false,
+            description_lists: false,
+            header_ids: true,
+            spoiler: true,
+            wikilinks: true,
+            gfm_quirks: true,
+            tasklist_classes: true,
+            smart: false,
+            hardbreaks: false,
+            unsafe_html: false,
+        }
+    }
+}
+
+impl MarkdownConfig {
+    pub fn load(path: &Path) -> Result<Self, RenderConfigError> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// Returns a copy with the fields a post sets in its front matter replaced.
+    pub fn with_overrides(&self, overrides: &MarkdownOverrides) -> Self {
+        let pick = |value: Option<bool>, default: bool| value.unwrap_or(default);
+
+        Self {
+            table: pick(overrides.table, self.table),
+            strikethrough: pick(overrides.strikethrough, self.strikethrough),
+            autolink: pick(overrides.autolink, self.autolink),
+            tasklist: pick(overrides.tasklist, self.tasklist),
+            footnotes: pick(overrides.footnotes, self.footnotes),
+            superscript: pick(overrides.superscript, self.superscript),
+            description_lists: pick(overrides.description_lists, self.description_lists),
+            header_ids: pick(overrides.header_ids, self.header_ids),
+            spoiler: pick(overrides.spoiler, self.spoiler),
+            wikilinks: pick(overrides.wikilinks, self.wikilinks),
+            gfm_quirks: pick(overrides.gfm_quirks, self.gfm_quirks),
+            tasklist_classes: pick(overrides.tasklist_classes, self.tasklist_classes),
+            smart: pick(overrides.smart, self.smart),
+            hardbreaks: pick(overrides.hardbreaks, self.hardbreaks),
+            unsafe_html: pick(overrides.unsafe_html, self.unsafe_html),
+        }
+    }
+}
+
+/// Per-post changes to [`MarkdownConfig`], set under `markdown` in front matter.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarkdownOverrides {
+    pub table: Option<bool>,
+    pub strikethrough: Option<bool>,
+    pub autolink: Option<bool>,
+    pub tasklist: Option<bool>,
+    pub footnotes: Option<bool>,
+    pub superscript: Option<bool>,
+    pub description_lists: Option<bool>,
+    pub header_ids: Option<bool>,
+    pub spoiler: Option<bool>,
+    pub wikilinks: Option<bool>,
+    pub gfm_quirks: Option<bool>,
+    pub tasklist_classes: Option<bool>,
+    pub smart: Option<bool>,
+    pub hardbreaks: Option<bool>,
+    pub unsafe_html: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, ValueEnum)]
+pub enum HighlightStyle {
+    /// Colors are written into each token's `style` attribute
+    Inline,
+    /// Tokens get CSS classes, colored by the generated `/static/highlight.css`
+    #[default]
+    Classes,
+}
+
+/// How fenced code blocks are highlighted on the server.
+#[derive(Debug, Clone)]
+pub struct HighlightConfig {
+    /// Name of one of syntect's bundled themes
+    pub theme: String,
+    pub style: HighlightStyle,
+}
+
+impl Default for HighlightConfig {
+    fn default() -> Self {
+        Self {
+            theme: String::from("InspiredGitHub"),
+            style: HighlightStyle::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MathMode {
+    /// KaTeX typesets `\(...\)`, `\[...\]` and ```` ```math ```` blocks in the browser
+    #[default]
+    Client,
+    /// `$...$`, `$$...$$` and ```` ```math ```` blocks become MathML on the server
+    Server,
+}
+
+/// The `math` front matter key: `false` turns math off for a post, `server`
+/// or `client` picks where it is typeset.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+pub enum MathSetting {
+    Enabled(bool),
+    Mode(MathMode),
+}
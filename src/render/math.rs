@@ -0,0 +1,41 @@
+use std::io::Write;
+
+use comrak::{create_formatter, html, nodes::NodeValue};
+use katex::{Opts, OutputType};
+
+// Typesets `$...$`, `$$...$$`, `` $`...`$ `` and ```` ```math ```` blocks as
+// MathML, leaving every other node to comrak's default HTML.
+create_formatter!(MathFormatter, {
+    NodeValue::Math(ref math) => |context, entering| {
+        if entering {
+            write_math(context, &math.literal, math.display_math)?;
+        }
+    },
+    NodeValue::CodeBlock(ref block) => |context, node, entering| {
+        if block.info.trim() != "math" {
+            return html::format_node_default(context, node, entering);
+        }
+
+        if entering {
+            write_math(context, &block.literal, true)?;
+        }
+    },
+});
+
+fn write_math(output: &mut dyn Write, tex: &str, display: bool) -> std::io::Result<()> {
+    let mut opts = Opts::default();
+    opts.set_display_mode(display);
+    opts.set_output_type(OutputType::Mathml);
+    opts.set_throw_on_error(false);
+    opts.add_macro(String::from("\\RR"), String::from("\\mathbb{R}"));
+
+    match katex::render_with_opts(tex, opts) {
+        Ok(mathml) => output.write_all(mathml.as_bytes()),
+        Err(error) => {
+            eprintln!("failed to typeset math: {}", error);
+            output.write_all(b"<code>")?;
+            html::escape(output, tex.as_bytes())?;
+            output.write_all(b"</code>")
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use comrak::{
+    Arena, ExtensionOptions, Options, ParseOptions, Plugins, RenderOptions, RenderPlugins, html,
+    nodes::NodeValue,
+    plugins::syntect::{SyntectAdapter, SyntectAdapterBuilder},
+};
+use syntect::highlighting::ThemeSet;
+use syntect::html::ClassStyle;
+
+use crate::render::config::{
+    HighlightConfig, HighlightStyle, MarkdownConfig, MarkdownOverrides, MathMode, MathSetting,
+};
+use crate::render::math::MathFormatter;
+use crate::render::toc::TocEntry;
+
+pub mod config;
+mod math;
+pub mod toc;
+
+/// A post turned into HTML.
+pub struct Rendered {
+    pub html: String,
+    /// Only set when the post asked for a table of contents
+    pub toc: Option<Vec<TocEntry>>,
+}
+
+/// Turns post markdown into HTML. Every page, whether public or protected,
+/// goes through the same configured options.
+pub struct Renderer {
+    config: MarkdownConfig,
+    /// Where math is typeset unless a post says otherwise
+    math: MathMode,
+    highlighter: SyntectAdapter,
+    /// Colors for the classes emitted in [`HighlightStyle::Classes`] mode
+    stylesheet: Option<String>,
+}
+
+/// Whether `name` is one of the themes bundled with syntect.
+pub fn is_known_theme(name: &str) -> bool {
+    ThemeSet::load_defaults().themes.contains_key(name)
+}
+
+impl Renderer {
+    /// Panics if the highlight theme is unknown; check it with [`is_known_theme`] first.
+    pub fn new(config: MarkdownConfig, highlight: HighlightConfig, math: MathMode) -> Self {
+        let (highlighter, stylesheet) = match highlight.style {
+            HighlightStyle::Inline => (
+                SyntectAdapterBuilder::new().theme(&highlight.theme).build(),
+                None,
+            ),
+            HighlightStyle::Classes => {
+                let themes = ThemeSet::load_defaults();
+                let stylesheet = syntect::html::css_for_theme_with_class_style(
+                    &themes.themes[&highlight.theme],
+                    ClassStyle::Spaced,
+                )
+                .expect("bundled themes convert to css");
+
+                (SyntectAdapterBuilder::new().css().build(), Some(stylesheet))
+            }
+        };
+
+        Self {
+            config,
+            math,
+            highlighter,
+            stylesheet,
+        }
+    }
+
+    /// Resolves a post's `math` setting against the configured mode; `None`
+    /// means math is off.
+    pub fn math_mode(&self, setting: Option<MathSetting>) -> Option<MathMode> {
+        match setting {
+            None | Some(MathSetting::Enabled(true)) => Some(self.math),
+            Some(MathSetting::Enabled(false)) => None,
+            Some(MathSetting::Mode(mode)) => Some(mode),
+        }
+    }
+
+    /// `toc` asks for a table of contents; when `None`, a `[[toc]]` line in
+    /// the post decides. The marker itself is never rendered.
+    pub fn render(
+        &self,
+        markdown: &str,
+        overrides: &MarkdownOverrides,
+        math: Option<MathMode>,
+        toc: Option<bool>,
+    ) -> Rendered {
+        let config = self.config.with_overrides(overrides);
+        let plugins = Plugins {
+            render: RenderPlugins {
+                codefence_syntax_highlighter: Some(&self.highlighter),
+                ..Default::default()
+            },
+        };
+
+        let mut options = options(&config);
+        if math == Some(MathMode::Server) {
+            options.extension.math_dollars = true;
+            options.extension.math_code = true;
+        }
+
+        let arena = Arena::new();
+        let root = comrak::parse_document(&arena, markdown, &options);
+
+        let marker = toc::take_marker(root);
+        let toc = toc.unwrap_or(marker).then(|| {
+            // The table of contents links to the heading anchors.
+            options.extension.header_ids.get_or_insert_with(String::new);
+            toc::collect(root)
+        });
+
+        let mut html = Vec::new();
+        if math == Some(MathMode::Server) {
+            MathFormatter::format_document_with_plugins(root, &options, &mut html, &plugins)
+        } else {
+            html::format_document_with_plugins(root, &options, &mut html, &plugins)
+        }
+        .expect("writing to a Vec does not fail");
+
+        Rendered {
+            html: String::from_utf8(html).expect("comrak writes UTF-8"),
+            toc,
+        }
+    }
+
+    /// Stylesheet for highlighted code, if code is highlighted with classes.
+    pub fn stylesheet(&self) -> Option<&str> {
+        self.stylesheet.as_deref()
+    }
+
+    /// Plain text of the first heading, used as the title of posts without one.
+    pub fn first_heading(&self, markdown: &str) -> Option<String> {
+        let arena = Arena::new();
+        let root = comrak::parse_document(&arena, markdown, &options(&self.config));
+
+        let heading = root
+            .descendants()
+            .find(|node| matches!(node.data.borrow().value, NodeValue::Heading(_)))?;
+
+        let mut title = String::new();
+        for node in heading.descendants() {
+            match &node.data.borrow().value {
+                NodeValue::Text(text) => title.push_str(text),
+                NodeValue::Code(code) => title.push_str(&code.literal),
+                NodeValue::SoftBreak | NodeValue::LineBreak => title.push(' '),
+                _ => {}
+            }
+        }
+
+        let title = title.trim();
+        (!title.is_empty()).then(|| title.to_string())
+    }
+
+    /// Plain text of the first paragraph, cut at a word boundary if it is
+    /// long, used to describe posts without a description in listings.
+    pub fn excerpt(&self, markdown: &str) -> Option<String> {
+        let arena = Arena::new();
+        let root = comrak::parse_document(&arena, markdown, &options(&self.config));
+
+        let paragraph = root
+            .children()
+            .find(|node| matches!(node.data.borrow().value, NodeValue::Paragraph))?;
+
+        let mut text = Vec::new();
+        html::collect_text(paragraph, &mut text);
+        let text = String::from_utf8_lossy(&text);
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+
+        if text.chars().count() <= EXCERPT_LEN {
+            return Some(text.to_string());
+        }
+
+        let cut: String = text.chars().take(EXCERPT_LEN).collect();
+        let cut = match cut.rfind(char::is_whitespace) {
+            Some(end) => &cut[..end],
+            None => &cut,
+        };
+        Some(format!("{}…", cut.trim_end()))
+    }
+}
+
+/// Longest excerpt shown in listings, in characters.
+const EXCERPT_LEN: usize = 240;
+
+fn options(config: &MarkdownConfig) -> Options<'static> {
+    Options {
+        extension: ExtensionOptions {
+            table: config.table,
+            strikethrough: config.strikethrough,
+            autolink: config.autolink,
+            tasklist: config.tasklist,
+            footnotes: config.footnotes,
+            superscript: config.superscript,
+            description_lists: config.description_lists,
+            header_ids: config.header_ids.then(String::new),
+            spoiler: config.spoiler,
+            wikilinks_title_after_pipe: config.wikilinks,
+            ..Default::default()
+        },
+        parse: ParseOptions {
+            smart: config.smart,
+            ..Default::default()
+        },
+        render: RenderOptions {
+            gfm_quirks: config.gfm_quirks,
+            tasklist_classes: config.tasklist_classes,
+            hardbreaks: config.hardbreaks,
+            unsafe_: config.unsafe_html,
+            ..Default::default()
+        },
+    }
+}
@@ -0,0 +1,90 @@
+use std::iter::Peekable;
+
+use comrak::{
+    html::{Anchorizer, collect_text},
+    nodes::{AstNode, NodeValue},
+};
+use serde::Serialize;
+
+/// A heading in the table of contents, with the headings nested below it.
+#[derive(Debug, Clone, Serialize)]
+pub struct TocEntry {
+    /// Anchor comrak gives the heading when header IDs are enabled
+    pub id: String,
+    pub title: String,
+    pub children: Vec<TocEntry>,
+}
+
+/// Removes a `[[toc]]` paragraph from the document, returning whether there was one.
+pub fn take_marker<'a>(root: &'a AstNode<'a>) -> bool {
+    let Some(marker) = root.children().find(|node| is_marker(node)) else {
+        return false;
+    };
+
+    marker.detach();
+    true
+}
+
+fn is_marker<'a>(node: &'a AstNode<'a>) -> bool {
+    if !matches!(node.data.borrow().value, NodeValue::Paragraph) {
+        return false;
+    }
+
+    let mut children = node.children();
+    if let (Some(child), None) = (children.next(), children.next())
+        && let NodeValue::WikiLink(ref link) = child.data.borrow().value
+    {
+        return link.url.eq_ignore_ascii_case("toc");
+    }
+
+    // Without the wikilinks extension the marker is plain text.
+    let only_text = node
+        .children()
+        .all(|child| matches!(child.data.borrow().value, NodeValue::Text(_)));
+    let mut text = Vec::new();
+    collect_text(node, &mut text);
+
+    only_text && text.trim_ascii().eq_ignore_ascii_case(b"[[toc]]")
+}
+
+/// Collects the headings of the document, nesting each under the closest
+/// preceding heading of a higher level.
+pub fn collect<'a>(root: &'a AstNode<'a>) -> Vec<TocEntry> {
+    let mut anchorizer = Anchorizer::new();
+    let mut headings = root
+        .descendants()
+        .filter_map(|node| {
+            let NodeValue::Heading(ref heading) = node.data.borrow().value else {
+                return None;
+            };
+
+            let mut text = Vec::new();
+            collect_text(node, &mut text);
+            let title = String::from_utf8_lossy(&text).into_owned();
+            // Anchors are numbered in document order, just like the renderer does.
+            let id = anchorizer.anchorize(title.clone());
+
+            Some((
+                heading.level,
+                TocEntry {
+                    id,
+                    title: title.trim().to_string(),
+                    children: Vec::new(),
+                },
+            ))
+        })
+        .peekable();
+
+    nest(&mut headings, 0)
+}
+
+fn nest(headings: &mut Peekable<impl Iterator<Item = (u8, TocEntry)>>, level: u8) -> Vec<TocEntry> {
+    let mut entries = Vec::new();
+
+    while let Some((entry_level, mut entry)) = headings.next_if(|(next, _)| *next > level) {
+        entry.children = nest(headings, entry_level);
+        entries.push(entry);
+    }
+
+    entries
+}
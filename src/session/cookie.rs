@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use time::OffsetDateTime;
+
+use crate::session::entity::SessionId;
+
+pub const SESSION_COOKIE: &str = "session_id";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Issues and verifies `session_id` cookies of the form `<id>.<signature>`.
+///
+/// New cookies are always signed with `key`; cookies signed with one of
+/// `previous_keys` are still accepted so the key can be rotated without
+/// logging every reader out.
+#[derive(Clone)]
+pub struct SessionCookies {
+    key: Vec<u8>,
+    previous_keys: Vec<Vec<u8>>,
+    same_site: SameSite,
+    domain: Option<String>,
+    secure: bool,
+    lifetime: Duration,
+}
+
+impl SessionCookies {
+    pub fn new(
+        key: Vec<u8>,
+        previous_keys: Vec<Vec<u8>>,
+        same_site: SameSite,
+        domain: Option<String>,
+        secure: bool,
+        lifetime: Duration,
+    ) -> Self {
+        Self {
+            key,
+            previous_keys,
+            same_site,
+            domain,
+            secure,
+            lifetime,
+        }
+    }
+
+    pub fn random_key() -> Vec<u8> {
+        let mut key = vec![0; 64];
+        OsRng.fill_bytes(&mut key);
+        key
+    }
+
+    /// Returns the session id from the jar if the cookie carries a valid signature.
+    pub fn session_id(&self, jar: &CookieJar) -> Option<SessionId> {
+        let value = jar.get(SESSION_COOKIE)?.value();
+        let (id, signature) = value.split_once('.')?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+
+        let verified = std::iter::once(&self.key)
+            .chain(self.previous_keys.iter())
+            .any(|key| {
+                let mut mac = Self::mac(key);
+                mac.update(id.as_bytes());
+                mac.verify_slice(&signature).is_ok()
+            });
+
+        if verified { id.parse().ok() } else { None }
+    }
+
+    pub fn build(&self, session_id: &SessionId) -> Cookie<'static> {
+        let id = session_id.to_string();
+        let mut mac = Self::mac(&self.key);
+        mac.update(id.as_bytes());
+        let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+
+        let lifetime = time::Duration::seconds(self.lifetime.as_secs() as i64);
+        let mut cookie = Cookie::build((SESSION_COOKIE, format!("{id}.{signature}")))
+            .http_only(true)
+            .secure(self.secure)
+            .path("/")
+            .same_site(self.same_site)
+            .max_age(lifetime)
+            .expires(OffsetDateTime::now_utc() + lifetime);
+
+        if let Some(domain) = &self.domain {
+            cookie = cookie.domain(domain.clone());
+        }
+
+        cookie.build()
+    }
+
+    fn mac(key: &[u8]) -> HmacSha256 {
+        HmacSha256::new_from_slice(key).expect("hmac accepts keys of any length")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"current key, at least thirty-two bytes long";
+    const OLD_KEY: &[u8] = b"previous key, at least thirty-two bytes long";
+
+    fn cookies(key: &[u8], previous_keys: &[&[u8]]) -> SessionCookies {
+        SessionCookies::new(
+            key.to_vec(),
+            previous_keys.iter().map(|key| key.to_vec()).collect(),
+            SameSite::Lax,
+            None,
+            false,
+            Duration::from_secs(60),
+        )
+    }
+
+    fn jar_with(value: String) -> CookieJar {
+        CookieJar::new().add(Cookie::new(SESSION_COOKIE, value))
+    }
+
+    #[test]
+    fn accepts_its_own_cookie() {
+        let cookies = cookies(KEY, &[]);
+        let session_id = SessionId::new();
+        let jar = CookieJar::new().add(cookies.build(&session_id));
+
+        assert_eq!(cookies.session_id(&jar), Some(session_id));
+    }
+
+    #[test]
+    fn rejects_a_tampered_cookie() {
+        let cookies = cookies(KEY, &[]);
+        let cookie = cookies.build(&SessionId::new());
+        let (_, signature) = cookie.value().split_once('.').unwrap();
+
+        let other_id = jar_with(format!("{}.{}", SessionId::new(), signature));
+        assert_eq!(cookies.session_id(&other_id), None);
+
+        let mut flipped = signature.to_string();
+        let first = if flipped.starts_with('A') { "B" } else { "A" };
+        flipped.replace_range(..1, first);
+        let id = cookie.value().split_once('.').unwrap().0;
+        assert_eq!(
+            cookies.session_id(&jar_with(format!("{id}.{flipped}"))),
+            None
+        );
+
+        assert_eq!(cookies.session_id(&jar_with(id.to_string())), None);
+        assert_eq!(cookies.session_id(&jar_with(format!("{id}."))), None);
+    }
+
+    #[test]
+    fn rejects_a_cookie_signed_with_another_key() {
+        let cookie = cookies(OLD_KEY, &[]).build(&SessionId::new());
+        let jar = CookieJar::new().add(cookie);
+
+        assert_eq!(cookies(KEY, &[]).session_id(&jar), None);
+    }
+
+    #[test]
+    fn accepts_a_cookie_signed_with_a_previous_key() {
+        let session_id = SessionId::new();
+        let jar = CookieJar::new().add(cookies(OLD_KEY, &[]).build(&session_id));
+        let rotated = cookies(KEY, &[OLD_KEY]);
+
+        assert_eq!(rotated.session_id(&jar), Some(session_id.clone()));
+
+        let reissued = rotated.build(&session_id);
+        let jar = CookieJar::new().add(reissued);
+        assert_eq!(cookies(OLD_KEY, &[]).session_id(&jar), None);
+        assert_eq!(cookies(KEY, &[]).session_id(&jar), Some(session_id));
+    }
+}
@@ -1,5 +1,5 @@
 use std::collections::HashSet;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use uuid::Uuid;
 
@@ -26,15 +26,17 @@ impl std::fmt::Display for SessionId {
     }
 }
 
-impl From<String> for SessionId {
-    fn from(value: String) -> Self {
-        SessionId(Uuid::parse_str(&value).unwrap())
+impl std::str::FromStr for SessionId {
+    type Err = uuid::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(value).map(SessionId)
     }
 }
 
-impl Into<String> for SessionId {
-    fn into(self) -> String {
-        self.0.to_string()
+impl From<SessionId> for String {
+    fn from(value: SessionId) -> Self {
+        value.0.to_string()
     }
 }
 
@@ -42,13 +44,43 @@ impl Into<String> for SessionId {
 pub struct SessionData {
     pub authorized_posts: HashSet<PostId>,
     pub created_at: Instant,
+    pub last_seen_at: Instant,
 }
 
 impl SessionData {
     pub fn new() -> Self {
+        let now = Instant::now();
         Self {
             authorized_posts: HashSet::new(),
-            created_at: Instant::now(),
+            created_at: now,
+            last_seen_at: now,
+        }
+    }
+
+    pub fn is_expired(&self, expiry: &SessionExpiry, now: Instant) -> bool {
+        expiry.is_expired(now - self.created_at, now - self.last_seen_at)
+    }
+}
+
+/// How long a session stays valid: `lifetime` counts from creation,
+/// `idle_timeout` from the last request that used the session.
+#[derive(Debug, Clone, Copy)]
+pub struct SessionExpiry {
+    pub lifetime: Duration,
+    pub idle_timeout: Duration,
+}
+
+impl SessionExpiry {
+    pub fn is_expired(&self, age: Duration, idle: Duration) -> bool {
+        age >= self.lifetime || idle >= self.idle_timeout
+    }
+}
+
+impl Default for SessionExpiry {
+    fn default() -> Self {
+        Self {
+            lifetime: Duration::from_secs(7 * 24 * 60 * 60),
+            idle_timeout: Duration::from_secs(24 * 60 * 60),
         }
     }
 }
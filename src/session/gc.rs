@@ -0,0 +1,34 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+
+use crate::session::store::SessionStore;
+
+/// Periodically evicts expired sessions so the store does not grow without bound.
+pub fn spawn_collector(
+    store: Arc<dyn SessionStore + Send + Sync>,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        let mut runs: u64 = 0;
+        let mut reaped: u64 = 0;
+
+        loop {
+            ticker.tick().await;
+            runs += 1;
+
+            match store.evict_expired().await {
+                Ok(0) => {}
+                Ok(count) => {
+                    reaped += count;
+                    println!(
+                        "reaped {count} expired session(s), {reaped} in {runs} collection(s) since start"
+                    );
+                }
+                Err(error) => eprintln!("failed to evict expired sessions: {}", error),
+            }
+        }
+    })
+}
@@ -1,2 +1,4 @@
+pub mod cookie;
 pub mod entity;
+pub mod gc;
 pub mod store;
@@ -1,44 +1,68 @@
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use async_trait::async_trait;
+use sqlx::SqlitePool;
 use thiserror::Error;
 
+use crate::clock::unix_now;
 use crate::post::entity::PostId;
-use crate::session::entity::{SessionData, SessionId};
+use crate::session::entity::{SessionData, SessionExpiry, SessionId};
 
 #[derive(Debug, Error)]
 pub enum SessionStoreError {
     #[error("lock poisoned: {0}")]
     LockPoisoned(String),
 
-    #[error("unexpected error")]
-    Unexpected,
+    #[error("database error: {0}")]
+    Database(String),
 }
 
+impl From<sqlx::Error> for SessionStoreError {
+    fn from(error: sqlx::Error) -> Self {
+        Self::Database(error.to_string())
+    }
+}
+
+#[async_trait]
 pub trait SessionStore {
-    fn create(
+    async fn create(
         &self,
         session_id: Option<SessionId>,
         post_id: PostId,
     ) -> Result<SessionId, SessionStoreError>;
-    fn authorized(&self, session_id: SessionId, post_id: PostId)
-    -> Result<bool, SessionStoreError>;
+    async fn authorized(
+        &self,
+        session_id: SessionId,
+        post_id: PostId,
+    ) -> Result<bool, SessionStoreError>;
+    /// Lists the posts a session has unlocked, none if it has expired.
+    async fn authorized_posts(
+        &self,
+        session_id: SessionId,
+    ) -> Result<Vec<PostId>, SessionStoreError>;
+    /// Removes every expired session and returns how many were removed.
+    async fn evict_expired(&self) -> Result<u64, SessionStoreError>;
 }
 
 pub struct MemorySessionStore {
     sessions: Arc<Mutex<HashMap<SessionId, SessionData>>>,
+    expiry: SessionExpiry,
 }
 
 impl MemorySessionStore {
-    pub fn new() -> Self {
+    pub fn new(expiry: SessionExpiry) -> Self {
         Self {
             sessions: Arc::from(Mutex::new(HashMap::new())),
+            expiry,
         }
     }
 }
 
+#[async_trait]
 impl SessionStore for MemorySessionStore {
-    fn create(
+    async fn create(
         &self,
         session_id: Option<SessionId>,
         post_id: PostId,
@@ -48,33 +72,240 @@ impl SessionStore for MemorySessionStore {
             .lock()
             .map_err(|error| SessionStoreError::LockPoisoned(error.to_string()))?;
 
-        let session_id = session_id.unwrap_or_default();
+        let now = Instant::now();
+        if let Some(session_id) = session_id
+            && let Some(existing) = sessions.get_mut(&session_id)
+        {
+            if !existing.is_expired(&self.expiry, now) {
+                existing.authorized_posts.insert(post_id);
+                existing.last_seen_at = now;
+                return Ok(session_id);
+            }
 
-        if let Some(existing) = sessions.get_mut(&session_id) {
-            existing.authorized_posts.insert(post_id);
-            return Ok(session_id);
+            sessions.remove(&session_id);
         }
 
+        let session_id = SessionId::new();
         let mut session_data = SessionData::new();
         session_data.authorized_posts.insert(post_id);
         sessions.insert(session_id.clone(), session_data);
         Ok(session_id)
     }
 
-    fn authorized(
+    async fn authorized(
         &self,
         session_id: SessionId,
         post_id: PostId,
     ) -> Result<bool, SessionStoreError> {
-        let sessions = self
+        let mut sessions = self
             .sessions
             .lock()
             .map_err(|error| SessionStoreError::LockPoisoned(error.to_string()))?;
 
-        Ok(if let Some(session_data) = sessions.get(&session_id) {
-            session_data.authorized_posts.contains(&post_id)
-        } else {
-            false
-        })
+        let now = Instant::now();
+        let Some(session_data) = sessions.get_mut(&session_id) else {
+            return Ok(false);
+        };
+
+        if session_data.is_expired(&self.expiry, now) {
+            sessions.remove(&session_id);
+            return Ok(false);
+        }
+
+        session_data.last_seen_at = now;
+        Ok(session_data.authorized_posts.contains(&post_id))
+    }
+
+    async fn authorized_posts(
+        &self,
+        session_id: SessionId,
+    ) -> Result<Vec<PostId>, SessionStoreError> {
+        let mut sessions = self
+            .sessions
+            .lock()
+            .map_err(|error| SessionStoreError::LockPoisoned(error.to_string()))?;
+
+        let now = Instant::now();
+        let Some(session_data) = sessions.get_mut(&session_id) else {
+            return Ok(Vec::new());
+        };
+
+        if session_data.is_expired(&self.expiry, now) {
+            sessions.remove(&session_id);
+            return Ok(Vec::new());
+        }
+
+        session_data.last_seen_at = now;
+        Ok(session_data.authorized_posts.iter().cloned().collect())
+    }
+
+    async fn evict_expired(&self) -> Result<u64, SessionStoreError> {
+        let mut sessions = self
+            .sessions
+            .lock()
+            .map_err(|error| SessionStoreError::LockPoisoned(error.to_string()))?;
+
+        let now = Instant::now();
+        let before = sessions.len();
+        sessions.retain(|_, session_data| !session_data.is_expired(&self.expiry, now));
+        Ok((before - sessions.len()) as u64)
+    }
+}
+
+pub struct SqliteSessionStore {
+    pool: SqlitePool,
+    expiry: SessionExpiry,
+}
+
+impl SqliteSessionStore {
+    pub fn new(pool: SqlitePool, expiry: SessionExpiry) -> Self {
+        Self { pool, expiry }
+    }
+
+    fn is_expired(&self, created_at: i64, last_seen_at: i64, now: i64) -> bool {
+        let elapsed = |since: i64| Duration::from_secs(now.saturating_sub(since).max(0) as u64);
+        self.expiry
+            .is_expired(elapsed(created_at), elapsed(last_seen_at))
+    }
+
+    async fn delete(&self, session_id: &SessionId) -> Result<(), SessionStoreError> {
+        sqlx::query("DELETE FROM sessions WHERE id = $1")
+            .bind(session_id.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SessionStore for SqliteSessionStore {
+    async fn create(
+        &self,
+        session_id: Option<SessionId>,
+        post_id: PostId,
+    ) -> Result<SessionId, SessionStoreError> {
+        let now = unix_now();
+        let existing: Option<(i64, i64)> = match &session_id {
+            Some(session_id) => {
+                sqlx::query_as("SELECT created_at, last_seen_at FROM sessions WHERE id = $1")
+                    .bind(session_id.to_string())
+                    .fetch_optional(&self.pool)
+                    .await?
+            }
+            None => None,
+        };
+
+        let session_id = match (session_id, existing) {
+            (Some(session_id), Some((created_at, last_seen_at)))
+                if !self.is_expired(created_at, last_seen_at, now) =>
+            {
+                session_id
+            }
+            (Some(session_id), Some(_)) => {
+                self.delete(&session_id).await?;
+                SessionId::new()
+            }
+            _ => SessionId::new(),
+        };
+
+        let mut transaction = self.pool.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO sessions (id, created_at, last_seen_at) VALUES ($1, $2, $2)
+            ON CONFLICT (id) DO UPDATE SET last_seen_at = excluded.last_seen_at",
+        )
+        .bind(session_id.to_string())
+        .bind(now)
+        .execute(&mut *transaction)
+        .await?;
+
+        sqlx::query("INSERT OR IGNORE INTO session_posts (session_id, post_id) VALUES ($1, $2)")
+            .bind(session_id.to_string())
+            .bind(post_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+        Ok(session_id)
+    }
+
+    async fn authorized(
+        &self,
+        session_id: SessionId,
+        post_id: PostId,
+    ) -> Result<bool, SessionStoreError> {
+        let now = unix_now();
+        let row: Option<(i64, i64, bool)> = sqlx::query_as(
+            "SELECT created_at, last_seen_at, EXISTS (
+                SELECT 1 FROM session_posts WHERE session_id = sessions.id AND post_id = $2
+            ) FROM sessions WHERE id = $1",
+        )
+        .bind(session_id.to_string())
+        .bind(post_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some((created_at, last_seen_at, authorized)) = row else {
+            return Ok(false);
+        };
+
+        if self.is_expired(created_at, last_seen_at, now) {
+            self.delete(&session_id).await?;
+            return Ok(false);
+        }
+
+        sqlx::query("UPDATE sessions SET last_seen_at = $1 WHERE id = $2")
+            .bind(now)
+            .bind(session_id.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(authorized)
+    }
+
+    async fn authorized_posts(
+        &self,
+        session_id: SessionId,
+    ) -> Result<Vec<PostId>, SessionStoreError> {
+        let now = unix_now();
+        let row: Option<(i64, i64)> =
+            sqlx::query_as("SELECT created_at, last_seen_at FROM sessions WHERE id = $1")
+                .bind(session_id.to_string())
+                .fetch_optional(&self.pool)
+                .await?;
+
+        let Some((created_at, last_seen_at)) = row else {
+            return Ok(Vec::new());
+        };
+
+        if self.is_expired(created_at, last_seen_at, now) {
+            self.delete(&session_id).await?;
+            return Ok(Vec::new());
+        }
+
+        sqlx::query("UPDATE sessions SET last_seen_at = $1 WHERE id = $2")
+            .bind(now)
+            .bind(session_id.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        let posts = sqlx::query_scalar("SELECT post_id FROM session_posts WHERE session_id = $1")
+            .bind(session_id.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(posts)
+    }
+
+    async fn evict_expired(&self) -> Result<u64, SessionStoreError> {
+        let now = unix_now();
+        let result =
+            sqlx::query("DELETE FROM sessions WHERE created_at <= $1 OR last_seen_at <= $2")
+                .bind(now - self.expiry.lifetime.as_secs() as i64)
+                .bind(now - self.expiry.idle_timeout.as_secs() as i64)
+                .execute(&self.pool)
+                .await?;
+
+        Ok(result.rows_affected())
     }
 }
@@ -0,0 +1,132 @@
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::RwLock;
+
+use minijinja::{Environment, ErrorKind};
+use serde::Serialize;
+
+pub mod watcher;
+
+/// Templates compiled into the binary, used for any template missing from
+/// the template directory and written out by `poster init`.
+pub const DEFAULT_TEMPLATES: &[(&str, &str)] = &[
+    ("base.html", include_str!("../../templates/base.html")),
+    ("message.html", include_str!("../../templates/message.html")),
+    ("post.html", include_str!("../../templates/post.html")),
+    ("index.html", include_str!("../../templates/index.html")),
+    ("tag.html", include_str!("../../templates/tag.html")),
+    ("tags.html", include_str!("../../templates/tags.html")),
+    ("search.html", include_str!("../../templates/search.html")),
+    (
+        "password.html",
+        include_str!("../../templates/password.html"),
+    ),
+    (
+        "not-found.html",
+        include_str!("../../templates/not-found.html"),
+    ),
+    ("expired.html", include_str!("../../templates/expired.html")),
+    (
+        "invalid-post.html",
+        include_str!("../../templates/invalid-post.html"),
+    ),
+];
+
+/// Renders pages from the templates in a directory, read when first used.
+/// Templates the directory does not have come from [`DEFAULT_TEMPLATES`].
+pub struct Theme {
+    path: PathBuf,
+    templates: RwLock<Environment<'static>>,
+    defaults: Environment<'static>,
+}
+
+impl Theme {
+    pub fn new(path: PathBuf) -> Self {
+        let mut templates = Environment::new();
+        let dir = path.clone();
+        templates.set_loader(move |name| load(&dir, name));
+
+        let mut defaults = Environment::new();
+        defaults.set_loader(|name| Ok(default_template(name).map(String::from)));
+
+        Self {
+            path,
+            templates: RwLock::new(templates),
+            defaults,
+        }
+    }
+
+    /// Whether `name` exists in the template directory or among the defaults.
+    pub fn has_template(&self, name: &str) -> bool {
+        default_template(name).is_some()
+            || template_path(&self.path, name).is_some_and(|path| path.is_file())
+    }
+
+    /// A customized default template that fails to render is reported and
+    /// replaced by the embedded one, so a broken theme does not take pages down.
+    pub fn render<S: Serialize>(&self, name: &str, context: S) -> Result<String, minijinja::Error> {
+        let result = {
+            let templates = self.templates.read().expect("theme lock poisoned");
+            templates
+                .get_template(name)
+                .and_then(|template| template.render(&context))
+        };
+
+        match result {
+            Ok(html) => Ok(html),
+            Err(error) if default_template(name).is_some() => {
+                eprintln!(
+                    "failed to render template {} from {}: {:#}",
+                    name,
+                    self.path.display(),
+                    error
+                );
+                self.defaults.get_template(name)?.render(context)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Forgets compiled templates so they are read again on next use.
+    pub fn reload(&self) {
+        self.templates
+            .write()
+            .expect("theme lock poisoned")
+            .clear_templates();
+    }
+}
+
+fn default_template(name: &str) -> Option<&'static str> {
+    DEFAULT_TEMPLATES
+        .iter()
+        .find(|(default, _)| *default == name)
+        .map(|(_, source)| *source)
+}
+
+/// Joins `name` onto the template directory, refusing names that would leave it.
+fn template_path(dir: &Path, name: &str) -> Option<PathBuf> {
+    let relative = Path::new(name);
+    relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+        .then(|| dir.join(relative))
+}
+
+fn load(dir: &Path, name: &str) -> Result<Option<String>, minijinja::Error> {
+    if let Some(path) = template_path(dir, name) {
+        match fs::read_to_string(&path) {
+            Ok(source) => return Ok(Some(source)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => {
+                return Err(minijinja::Error::new(
+                    ErrorKind::InvalidOperation,
+                    format!("failed to read {}", path.display()),
+                )
+                .with_source(error));
+            }
+        }
+    }
+
+    Ok(default_template(name).map(String::from))
+}
@@ -0,0 +1,36 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use notify::{EventKind, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::theme::Theme;
+
+/// Reloads the theme whenever a file in the template directory changes, so
+/// templates can be edited without restarting the server.
+pub fn spawn_watcher(theme: Arc<Theme>, path: PathBuf) -> notify::Result<JoinHandle<()>> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = sender.send(event);
+    })?;
+    watcher.watch(&path, RecursiveMode::Recursive)?;
+
+    Ok(tokio::spawn(async move {
+        // Dropping the watcher would stop the events.
+        let _watcher = watcher;
+
+        while let Some(event) = receiver.recv().await {
+            match event {
+                Ok(event) if matches!(event.kind, EventKind::Access(_)) => {}
+                Ok(_) => {
+                    // An editor saving a file emits several events at once.
+                    while receiver.try_recv().is_ok() {}
+                    theme.reload();
+                    println!("reloaded templates from {}", path.display());
+                }
+                Err(error) => eprintln!("failed to watch templates: {}", error),
+            }
+        }
+    }))
+}
@@ -0,0 +1,159 @@
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, sqlx::Encode, sqlx::Decode)]
+#[serde(transparent)]
+pub struct TokenId(String);
+
+impl TokenId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4().simple().to_string())
+    }
+}
+
+impl std::fmt::Display for TokenId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<String> for TokenId {
+    fn from(value: String) -> Self {
+        TokenId(value)
+    }
+}
+
+impl sqlx::Type<sqlx::Sqlite> for TokenId {
+    fn type_info() -> <sqlx::Sqlite as sqlx::Database>::TypeInfo {
+        <String as sqlx::Type<sqlx::Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &<sqlx::Sqlite as sqlx::Database>::TypeInfo) -> bool {
+        <String as sqlx::Type<sqlx::Sqlite>>::compatible(ty)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Read,
+    Write,
+    Delete,
+    /// Manage tokens; implies every other scope
+    Admin,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::Delete => "delete",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+impl std::fmt::Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "read" => Ok(Self::Read),
+            "write" => Ok(Self::Write),
+            "delete" => Ok(Self::Delete),
+            "admin" => Ok(Self::Admin),
+            _ => Err(format!("unknown scope: {value}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiToken {
+    pub id: TokenId,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: i64,
+    pub last_used_at: Option<i64>,
+    pub revoked_at: Option<i64>,
+}
+
+impl ApiToken {
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.scopes
+            .iter()
+            .any(|granted| *granted == scope || *granted == Scope::Admin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Scope; 4] = [Scope::Read, Scope::Write, Scope::Delete, Scope::Admin];
+
+    fn token(scopes: &[Scope]) -> ApiToken {
+        ApiToken {
+            id: TokenId::new(),
+            name: "ci".to_string(),
+            scopes: scopes.to_vec(),
+            created_at: 0,
+            last_used_at: None,
+            revoked_at: None,
+        }
+    }
+
+    #[test]
+    fn admin_allows_every_scope() {
+        let admin = token(&[Scope::Admin]);
+
+        assert!(ALL.into_iter().all(|scope| admin.allows(scope)));
+    }
+
+    #[test]
+    fn other_scopes_allow_only_themselves() {
+        for granted in [Scope::Read, Scope::Write, Scope::Delete] {
+            let token = token(&[granted]);
+
+            for scope in ALL {
+                assert_eq!(token.allows(scope), scope == granted, "{granted} / {scope}");
+            }
+        }
+    }
+
+    #[test]
+    fn combines_granted_scopes() {
+        let token = token(&[Scope::Read, Scope::Write]);
+
+        assert!(token.allows(Scope::Read));
+        assert!(token.allows(Scope::Write));
+        assert!(!token.allows(Scope::Delete));
+        assert!(!token.allows(Scope::Admin));
+    }
+
+    #[test]
+    fn allows_nothing_without_scopes() {
+        let token = token(&[]);
+
+        assert!(ALL.into_iter().all(|scope| !token.allows(scope)));
+    }
+
+    #[test]
+    fn parses_scope_names() {
+        for scope in ALL {
+            assert_eq!(scope.to_string().parse::<Scope>(), Ok(scope));
+        }
+
+        assert!("Admin".parse::<Scope>().is_err());
+        assert!("".parse::<Scope>().is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use thiserror::Error;
+
+use crate::clock::unix_now;
+use crate::token::entity::{ApiToken, Scope, TokenId};
+
+const TOKEN_PREFIX: &str = "poster_";
+
+#[derive(Debug, Error)]
+pub enum TokenStoreError {
+    #[error("database error: {0}")]
+    Database(String),
+
+    #[error("invalid stored scope: {0}")]
+    InvalidScope(String),
+}
+
+impl From<sqlx::Error> for TokenStoreError {
+    fn from(error: sqlx::Error) -> Self {
+        Self::Database(error.to_string())
+    }
+}
+
+pub trait TokenStore {
+    /// Creates a token and returns it together with its secret, which is not stored.
+    async fn create(
+        &self,
+        name: String,
+        scopes: Vec<Scope>,
+    ) -> Result<(ApiToken, String), TokenStoreError>;
+    /// Resolves a bearer secret to a live token and records its use.
+    async fn authenticate(&self, secret: &str) -> Result<Option<ApiToken>, TokenStoreError>;
+    async fn list(&self) -> Result<Vec<ApiToken>, TokenStoreError>;
+    async fn revoke(&self, id: TokenId) -> Result<bool, TokenStoreError>;
+}
+
+pub struct SqliteTokenStore {
+    pool: SqlitePool,
+}
+
+impl SqliteTokenStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+/// Secrets carry 256 bits of entropy, so a plain digest is enough to store them.
+fn hash_secret(secret: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(secret.as_bytes()))
+}
+
+fn generate_secret() -> String {
+    let mut bytes = [0; 32];
+    OsRng.fill_bytes(&mut bytes);
+    format!("{TOKEN_PREFIX}{}", URL_SAFE_NO_PAD.encode(bytes))
+}
+
+type TokenRow = (TokenId, String, String, i64, Option<i64>, Option<i64>);
+
+fn from_row(row: TokenRow) -> Result<ApiToken, TokenStoreError> {
+    let (id, name, scopes, created_at, last_used_at, revoked_at) = row;
+    let scopes = scopes
+        .split(',')
+        .filter(|scope| !scope.is_empty())
+        .map(|scope| scope.parse().map_err(TokenStoreError::InvalidScope))
+        .collect::<Result<Vec<Scope>, _>>()?;
+
+    Ok(ApiToken {
+        id,
+        name,
+        scopes,
+        created_at,
+        last_used_at,
+        revoked_at,
+    })
+}
+
+impl TokenStore for SqliteTokenStore {
+    async fn create(
+        &self,
+        name: String,
+        scopes: Vec<Scope>,
+    ) -> Result<(ApiToken, String), TokenStoreError> {
+        let secret = generate_secret();
+        let token = ApiToken {
+            id: TokenId::new(),
+            name,
+            scopes,
+            created_at: unix_now(),
+            last_used_at: None,
+            revoked_at: None,
+        };
+
+        let scopes = token
+            .scopes
+            .iter()
+            .map(Scope::as_str)
+            .collect::<Vec<_>>()
+            .join(",");
+
+        sqlx::query(
+            "INSERT INTO api_tokens (id, name, token_hash, scopes, created_at)
+            VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(token.id.clone())
+        .bind(&token.name)
+        .bind(hash_secret(&secret))
+        .bind(scopes)
+        .bind(token.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok((token, secret))
+    }
+
+    async fn authenticate(&self, secret: &str) -> Result<Option<ApiToken>, TokenStoreError> {
+        let now = unix_now();
+        let row: Option<TokenRow> = sqlx::query_as(
+            "UPDATE api_tokens SET last_used_at = $1
+            WHERE token_hash = $2 AND revoked_at IS NULL
+            RETURNING id, name, scopes, created_at, last_used_at, revoked_at",
+        )
+        .bind(now)
+        .bind(hash_secret(secret))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(from_row).transpose()
+    }
+
+    async fn list(&self) -> Result<Vec<ApiToken>, TokenStoreError> {
+        let rows: Vec<TokenRow> = sqlx::query_as(
+            "SELECT id, name, scopes, created_at, last_used_at, revoked_at FROM api_tokens
+            ORDER BY created_at",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(from_row).collect()
+    }
+
+    async fn revoke(&self, id: TokenId) -> Result<bool, TokenStoreError> {
+        let result = sqlx::query(
+            "UPDATE api_tokens SET revoked_at = $1 WHERE id = $2 AND revoked_at IS NULL",
+        )
+        .bind(unix_now())
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}